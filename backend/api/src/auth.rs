@@ -0,0 +1,58 @@
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::{header::AUTHORIZATION, request::Parts},
+};
+use secrecy::ExposeSecret;
+
+use crate::{Error, Result};
+use config_structs::ApiState;
+
+/// Guards the admin endpoints, expects an `Authorization: Token <admin_token>` header.
+///
+/// The admin endpoints are disabled when no `admin_token` is configured.
+pub struct Admin;
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Admin
+where
+    S: Send + Sync,
+    ApiState: FromRef<S>,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+        let ApiState { admin_token, .. } = ApiState::from_ref(state);
+        let admin_token = admin_token
+            .filter(|token| !token.expose_secret().is_empty())
+            .ok_or(Error::Forbidden)?;
+
+        let token = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Token "))
+            .ok_or(Error::Unauthorized)?;
+        if constant_time_eq(token.as_bytes(), admin_token.expose_secret().as_bytes()) {
+            Ok(Self)
+        } else {
+            Err(Error::Unauthorized)
+        }
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::constant_time_eq;
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"token", b"token"));
+        assert!(!constant_time_eq(b"token", b"tokem"));
+        assert!(!constant_time_eq(b"token", b"token2"));
+    }
+}
@@ -0,0 +1,166 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+
+use crate::{auth::Admin, Error, Result, ResultExt};
+use config_structs::ApiState;
+use etl::{
+    blacklist::{BlockKind, MatchMode},
+    purge_blacklisted, PurgeReport,
+};
+
+#[derive(serde::Serialize)]
+pub(crate) struct BlacklistEntry {
+    id: i32,
+    kind: BlockKind,
+    pattern: String,
+    match_mode: MatchMode,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct NewBlacklistEntry {
+    kind: BlockKind,
+    pattern: String,
+    #[serde(default)]
+    match_mode: MatchMode,
+}
+
+impl NewBlacklistEntry {
+    fn validate(self) -> Result<Self> {
+        self.match_mode
+            .validate(&self.pattern)
+            .map_err(|e| Error::unprocessable_entity([("pattern", e)]))?;
+        Ok(self)
+    }
+}
+
+fn duplicate(_: Box<dyn sqlx::error::DatabaseError>) -> Error {
+    Error::unprocessable_entity([("pattern", "is already blacklisted")])
+}
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn list_blacklist(
+    _: Admin,
+    State(ApiState { db_pool, .. }): State<ApiState>,
+) -> Result<Json<Vec<BlacklistEntry>>> {
+    Ok(Json(
+        sqlx::query_as!(
+            BlacklistEntry,
+            r#"SELECT
+                id,
+                kind AS "kind: BlockKind",
+                pattern,
+                match_mode AS "match_mode: MatchMode",
+                created_at
+            FROM blacklist
+            ORDER BY id"#
+        )
+        .fetch_all(&db_pool)
+        .await?,
+    ))
+}
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn create_blacklist_entry(
+    _: Admin,
+    State(ApiState { db_pool, .. }): State<ApiState>,
+    Json(entry): Json<NewBlacklistEntry>,
+) -> Result<(StatusCode, Json<BlacklistEntry>)> {
+    let NewBlacklistEntry {
+        kind,
+        pattern,
+        match_mode,
+    } = entry.validate()?;
+    let entry = sqlx::query_as!(
+        BlacklistEntry,
+        r#"INSERT INTO blacklist (kind, pattern, match_mode)
+        VALUES ($1, $2, $3)
+        RETURNING
+            id,
+            kind AS "kind: BlockKind",
+            pattern,
+            match_mode AS "match_mode: MatchMode",
+            created_at"#,
+        kind as BlockKind,
+        pattern,
+        match_mode as MatchMode
+    )
+    .fetch_one(&db_pool)
+    .await
+    .on_constraint("blacklist_kind_pattern_match_mode_key", duplicate)?;
+    Ok((StatusCode::CREATED, Json(entry)))
+}
+
+#[tracing::instrument(skip(db_pool, entry))]
+pub(crate) async fn update_blacklist_entry(
+    _: Admin,
+    State(ApiState { db_pool, .. }): State<ApiState>,
+    Path(id): Path<i32>,
+    Json(entry): Json<NewBlacklistEntry>,
+) -> Result<Json<BlacklistEntry>> {
+    let NewBlacklistEntry {
+        kind,
+        pattern,
+        match_mode,
+    } = entry.validate()?;
+    let entry = sqlx::query_as!(
+        BlacklistEntry,
+        r#"UPDATE blacklist
+        SET kind = $2, pattern = $3, match_mode = $4
+        WHERE id = $1
+        RETURNING
+            id,
+            kind AS "kind: BlockKind",
+            pattern,
+            match_mode AS "match_mode: MatchMode",
+            created_at"#,
+        id,
+        kind as BlockKind,
+        pattern,
+        match_mode as MatchMode
+    )
+    .fetch_optional(&db_pool)
+    .await
+    .on_constraint("blacklist_kind_pattern_match_mode_key", duplicate)?
+    .ok_or(Error::NotFound)?;
+    Ok(Json(entry))
+}
+
+#[tracing::instrument(skip(db_pool))]
+pub(crate) async fn delete_blacklist_entry(
+    _: Admin,
+    State(ApiState { db_pool, .. }): State<ApiState>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode> {
+    let deleted = sqlx::query!("DELETE FROM blacklist WHERE id = $1", id)
+        .execute(&db_pool)
+        .await?
+        .rows_affected();
+    if deleted == 0 {
+        return Err(Error::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct PurgeQuery {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Hides the stored posts that match the current blacklist.
+#[tracing::instrument(skip(db_pool, blacklist))]
+pub(crate) async fn purge_blacklist(
+    _: Admin,
+    State(ApiState {
+        db_pool, blacklist, ..
+    }): State<ApiState>,
+    Query(PurgeQuery { dry_run }): Query<PurgeQuery>,
+) -> Result<Json<PurgeReport>> {
+    Ok(Json(
+        purge_blacklisted(&db_pool, &blacklist, dry_run).await?,
+    ))
+}
@@ -0,0 +1,115 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, State},
+    http::{request::Parts, HeaderName, StatusCode},
+    Json,
+};
+
+use crate::{Error, Result};
+use config_structs::ApiState;
+use etl::blacklist::BlockKind;
+
+/// Header holding the key that identifies a user, generated by the frontend and
+/// shared between devices by copying it.
+const USER_KEY: HeaderName = HeaderName::from_static("x-user-key");
+
+/// The key is a bearer secret: there are no accounts behind it, whoever sends
+/// it reads and edits that blocklist. It must stay unguessable, which is why
+/// the frontend uses a random UUID, and it is never sent back by the API.
+pub struct UserKey(pub String);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for UserKey {
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self> {
+        parts
+            .headers
+            .get(USER_KEY)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|key| !key.is_empty() && key.len() <= 128)
+            .map(|key| Self(key.to_owned()))
+            .ok_or(Error::Unauthorized)
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct Block {
+    kind: BlockKind,
+    value: String,
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct Blocklist {
+    authors: Vec<String>,
+    tags: Vec<String>,
+}
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn get_blocklist(
+    State(ApiState { db_pool, .. }): State<ApiState>,
+    UserKey(user_key): UserKey,
+) -> Result<Json<Blocklist>> {
+    let row = sqlx::query!(
+        r#"SELECT
+            COALESCE(array_agg(value ORDER BY created_at) FILTER (WHERE kind = 'author'), '{}') AS "authors!",
+            COALESCE(array_agg(value ORDER BY created_at) FILTER (WHERE kind = 'tag'), '{}') AS "tags!"
+        FROM user_blocklist
+        WHERE user_key = $1"#,
+        user_key
+    )
+    .fetch_one(&db_pool)
+    .await?;
+    Ok(Json(Blocklist {
+        authors: row.authors,
+        tags: row.tags,
+    }))
+}
+
+#[tracing::instrument(skip(db_pool, user_key))]
+pub(crate) async fn block(
+    State(ApiState { db_pool, .. }): State<ApiState>,
+    UserKey(user_key): UserKey,
+    Json(Block { kind, value }): Json<Block>,
+) -> Result<StatusCode> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(Error::unprocessable_entity([(
+            "value",
+            "must not be empty",
+        )]));
+    }
+    sqlx::query!(
+        r#"INSERT INTO user_blocklist (user_key, kind, value)
+        VALUES ($1, $2, $3)
+        ON CONFLICT DO NOTHING"#,
+        user_key,
+        kind as BlockKind,
+        value
+    )
+    .execute(&db_pool)
+    .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[tracing::instrument(skip(db_pool, user_key))]
+pub(crate) async fn unblock(
+    State(ApiState { db_pool, .. }): State<ApiState>,
+    UserKey(user_key): UserKey,
+    Json(Block { kind, value }): Json<Block>,
+) -> Result<StatusCode> {
+    let deleted = sqlx::query!(
+        "DELETE FROM user_blocklist WHERE user_key = $1 AND kind = $2 AND value = $3",
+        user_key,
+        kind as BlockKind,
+        value.trim()
+    )
+    .execute(&db_pool)
+    .await?
+    .rows_affected();
+    if deleted == 0 {
+        return Err(Error::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,212 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::SystemTime,
+};
+
+use lru::LruCache;
+
+use config_structs::ImageCacheSettings;
+
+/// On-disk cache for the proxied images. Files are evicted in LRU order once
+/// the total size goes over the configured cap.
+#[derive(Clone)]
+pub struct ImageCache(Arc<Inner>);
+
+struct Inner {
+    dir: PathBuf,
+    max_size: u64,
+    index: Mutex<Index>,
+    /// Numbers the temporary files of the writes in progress.
+    next_tmp: AtomicU64,
+}
+
+struct Index {
+    entries: LruCache<String, u64>,
+    total_size: u64,
+}
+
+impl ImageCache {
+    /// Creates the cache directory if needed and indexes the files already in
+    /// it, treating the least recently modified ones as the least recently used.
+    pub async fn open(settings: &ImageCacheSettings) -> std::io::Result<Self> {
+        let dir = PathBuf::from(&settings.dir);
+        tokio::fs::create_dir_all(&dir).await?;
+
+        let mut files = Vec::new();
+        let mut read_dir = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = read_dir.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            if !metadata.is_file() {
+                continue;
+            }
+            let file_name = entry.file_name();
+            let Some(key) = file_name.to_str() else {
+                continue;
+            };
+            if key.starts_with('.') {
+                // Leftover of an interrupted write.
+                let _ = tokio::fs::remove_file(entry.path()).await;
+            } else {
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                files.push((modified, key.to_owned(), metadata.len()));
+            }
+        }
+        files.sort();
+
+        let mut index = Index {
+            entries: LruCache::unbounded(),
+            total_size: 0,
+        };
+        for (_, key, size) in files {
+            index.total_size += size;
+            index.entries.put(key, size);
+        }
+
+        let cache = Self(Arc::new(Inner {
+            dir,
+            max_size: settings.max_size_mb * 1024 * 1024,
+            index: Mutex::new(index),
+            next_tmp: AtomicU64::new(0),
+        }));
+        cache.evict().await;
+        Ok(cache)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get(&self, key: &str) -> Option<Vec<u8>> {
+        self.index().entries.get(key)?;
+        match tokio::fs::read(self.path(key)).await {
+            Ok(bytes) => Some(bytes),
+            Err(e) => {
+                tracing::warn!("Cached image {} is unreadable, dropping it: {:?}", key, e);
+                let mut index = self.index();
+                if let Some(size) = index.entries.pop(key) {
+                    index.total_size -= size;
+                }
+                None
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self, bytes))]
+    pub async fn insert(&self, key: &str, bytes: &[u8]) -> std::io::Result<()> {
+        let size = bytes.len() as u64;
+        if size > self.0.max_size {
+            return Ok(());
+        }
+
+        // Write to a temporary file first so readers never see a partial image.
+        // Concurrent misses on the same key each get their own file.
+        let tmp = self.0.next_tmp.fetch_add(1, Ordering::Relaxed);
+        let tmp_path = self.0.dir.join(format!(".{key}.{tmp}.tmp"));
+        tokio::fs::write(&tmp_path, bytes).await?;
+        tokio::fs::rename(&tmp_path, self.path(key)).await?;
+
+        {
+            let mut index = self.index();
+            if let Some(old_size) = index.entries.put(key.to_owned(), size) {
+                index.total_size -= old_size;
+            }
+            index.total_size += size;
+        }
+        self.evict().await;
+        Ok(())
+    }
+
+    async fn evict(&self) {
+        let evicted = {
+            let mut index = self.index();
+            let mut evicted = Vec::new();
+            while index.total_size > self.0.max_size {
+                let Some((key, size)) = index.entries.pop_lru() else {
+                    break;
+                };
+                index.total_size -= size;
+                evicted.push(key);
+            }
+            evicted
+        };
+        for key in evicted {
+            if let Err(e) = tokio::fs::remove_file(self.path(&key)).await {
+                tracing::warn!("Failed to remove evicted image {}: {:?}", key, e);
+            }
+        }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.0.dir.join(key)
+    }
+
+    fn index(&self) -> std::sync::MutexGuard<'_, Index> {
+        self.0
+            .index
+            .lock()
+            .expect("Image cache index lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn open_cache(name: &str, max_size_mb: u64) -> ImageCache {
+        let dir = std::env::temp_dir().join(format!("image-cache-{}-{}", name, std::process::id()));
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+        ImageCache::open(&ImageCacheSettings {
+            dir: dir.to_string_lossy().into_owned(),
+            max_size_mb,
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_evicts_least_recently_used() {
+        let cache = open_cache("lru", 1).await;
+        let half = vec![0u8; 512 * 1024];
+
+        cache.insert("first", &half).await.unwrap();
+        cache.insert("second", &half).await.unwrap();
+        // Touch "first" so that "second" becomes the eviction candidate.
+        assert!(cache.get("first").await.is_some());
+        cache.insert("third", &half).await.unwrap();
+
+        assert!(cache.get("first").await.is_some());
+        assert!(cache.get("second").await.is_none());
+        assert!(cache.get("third").await.is_some());
+        assert!(!cache.path("second").exists());
+    }
+
+    #[tokio::test]
+    async fn test_reopen_keeps_cached_files() {
+        let cache = open_cache("reopen", 1).await;
+        cache.insert("image", b"bytes").await.unwrap();
+
+        let reopened = ImageCache::open(&ImageCacheSettings {
+            dir: cache.0.dir.to_string_lossy().into_owned(),
+            max_size_mb: 1,
+        })
+        .await
+        .unwrap();
+        assert_eq!(reopened.get("image").await.unwrap(), b"bytes");
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_inserts_of_the_same_key() {
+        let cache = open_cache("concurrent", 1).await;
+        let inserts = (0..8u8).map(|i| {
+            let cache = cache.clone();
+            tokio::spawn(async move { cache.insert("image", &[i; 1024]).await })
+        });
+        for insert in inserts {
+            insert.await.unwrap().unwrap();
+        }
+
+        assert_eq!(cache.get("image").await.unwrap().len(), 1024);
+        assert_eq!(cache.index().total_size, 1024);
+    }
+}
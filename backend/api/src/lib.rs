@@ -1,7 +1,16 @@
+mod auth;
+mod blacklist;
+mod blocklist;
+mod image_cache;
 mod load;
+mod pagination;
+mod pixiv_proxy;
+mod runs;
+mod search;
 mod site_routes;
+mod sources;
 pub mod startup;
 
-use errors::Error;
+use errors::{Error, ResultExt};
 
 type Result<T, E = Error> = std::result::Result<T, E>;
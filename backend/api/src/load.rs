@@ -1,83 +1,195 @@
+use std::collections::HashMap;
+
 use axum::{
     extract::{Query, State},
-    Json,
+    response::Response,
+};
+use sqlx::{types::Json, PgPool};
+
+use crate::{
+    blocklist::UserKey,
+    pagination::{Cursor, Page, PageQuery},
+    Error, Result,
 };
-use sqlx::PgPool;
+use config_structs::{ApiState, SourceSettings};
+use etl::{enabled_sources, MediaKind, Post, PostImage, PostSource};
 
-use crate::Result;
-use config_structs::ApiState;
-use etl::{Post, PostSource};
+pub(crate) struct PostRow {
+    pub id: i32,
+    pub post_link: String,
+    pub preview_link: String,
+    pub images_number: i32,
+    pub media_kind: MediaKind,
+    pub created: chrono::DateTime<chrono::Utc>,
+    pub author: String,
+    pub author_link: String,
+    pub source: PostSource,
+    pub tags: Option<Vec<String>>,
+    pub author_profile_image: Option<String>,
+    pub images: Json<Vec<PostImage>>,
+    pub alternates: Vec<String>,
+}
 
-#[derive(serde::Deserialize)]
-pub struct Page {
-    page: i64,
+/// A post as served by the feeds.
+#[derive(serde::Serialize)]
+pub(crate) struct FeedPost {
+    #[serde(flatten)]
+    post: Post,
+    /// Links of the same artwork posted elsewhere, which the feeds leave out.
+    alternates: Vec<String>,
+}
+
+impl From<PostRow> for (Cursor, FeedPost) {
+    fn from(row: PostRow) -> Self {
+        let post = Post {
+            post_link: row.post_link,
+            preview_link: row.preview_link,
+            images: row.images.0,
+            images_number: row.images_number,
+            media_kind: row.media_kind,
+            created: row.created,
+            author: row.author,
+            author_link: row.author_link,
+            source: row.source,
+            tags: row.tags,
+            author_profile_image: row.author_profile_image,
+        };
+        (
+            Cursor {
+                id: row.id,
+                created: Some(row.created),
+            },
+            FeedPost {
+                post,
+                alternates: row.alternates,
+            },
+        )
+    }
 }
 
 #[tracing::instrument(skip_all)]
 pub async fn load_honkai_posts(
-    State(ApiState { db_pool, .. }): State<ApiState>,
-    page: Query<Page>,
-) -> Result<Json<Vec<Post>>> {
-    Ok(Json(
-        sqlx::query_as!(
-            Post,
-            r#"SELECT
-            post_link,
-            preview_link,
-            images_number,
-            created,
-            author,
-            author_link,
-            source AS "source!: PostSource",
-            tags,
-            author_profile_image
-        FROM honkai_posts
-        WHERE source != 'twitterhome'
-        ORDER BY id DESC
-        LIMIT 20 OFFSET $1"#,
-            page.page * 20
-        )
-        .fetch_all(&db_pool)
-        .await?,
-    ))
+    State(ApiState {
+        db_pool,
+        pagination,
+        ..
+    }): State<ApiState>,
+    user_key: Option<UserKey>,
+    Query(query): Query<PageQuery>,
+) -> Result<Response> {
+    let page = query.resolve(&pagination)?;
+    let posts = load_posts(&db_pool, false, &page, user_key.map(|k| k.0)).await?;
+    Ok(page.into_response(posts))
 }
 
 #[tracing::instrument(skip_all)]
 pub async fn load_twitter_home_posts(
-    State(ApiState { db_pool, .. }): State<ApiState>,
-    page: Query<Page>,
-) -> Result<Json<Vec<Post>>> {
-    Ok(Json(
-        sqlx::query_as!(
-            Post,
-            r#"SELECT
+    State(ApiState {
+        db_pool,
+        pagination,
+        ..
+    }): State<ApiState>,
+    user_key: Option<UserKey>,
+    Query(query): Query<PageQuery>,
+) -> Result<Response> {
+    let page = query.resolve(&pagination)?;
+    let posts = load_posts(&db_pool, true, &page, user_key.map(|k| k.0)).await?;
+    Ok(page.into_response(posts))
+}
+
+async fn load_posts(
+    db_pool: &PgPool,
+    twitter_home: bool,
+    page: &Page,
+    user_key: Option<String>,
+) -> Result<Vec<(Cursor, FeedPost)>> {
+    Ok(sqlx::query_as!(
+        PostRow,
+        r#"SELECT
+            id,
             post_link,
             preview_link,
             images_number,
+            media_kind AS "media_kind!: MediaKind",
             created,
             author,
             author_link,
             source AS "source!: PostSource",
             tags,
-            author_profile_image
+            author_profile_image,
+            COALESCE(
+                (SELECT JSONB_AGG(
+                    JSONB_BUILD_OBJECT(
+                        'url', url, 'width', width, 'height', height, 'variants', variants
+                    )
+                    ORDER BY position
+                ) FROM post_images WHERE post_id = honkai_posts.id),
+                '[]'
+            ) AS "images!: Json<Vec<PostImage>>",
+            ARRAY(
+                SELECT d.post_link FROM honkai_posts d
+                WHERE d.duplicate_of = honkai_posts.id AND d.hidden_at IS NULL
+                ORDER BY d.id
+            ) AS "alternates!"
         FROM honkai_posts
-        WHERE source = 'twitterhome'
-        ORDER BY id DESC
-        LIMIT 20 OFFSET $1"#,
-            page.page * 20
-        )
-        .fetch_all(&db_pool)
-        .await?,
-    ))
+        WHERE hidden_at IS NULL
+            AND duplicate_of IS NULL
+            AND (source = 'twitterhome') = $1
+            AND ($2::INT IS NULL
+                OR CASE WHEN $6 THEN (created, id) < ($7, $2) ELSE id < $2 END)
+            AND NOT EXISTS (
+                SELECT 1 FROM user_blocklist b
+                WHERE b.user_key = $5
+                    AND ((b.kind = 'author' AND b.value = honkai_posts.author)
+                        OR (b.kind = 'tag' AND b.value = ANY(honkai_posts.tags)))
+            )
+        ORDER BY CASE WHEN $6 THEN created END DESC, id DESC
+        LIMIT $3 OFFSET $4"#,
+        twitter_home,
+        page.before_id,
+        page.limit,
+        page.offset,
+        user_key,
+        page.by_created(),
+        page.before_created
+    )
+    .fetch_all(db_pool)
+    .await?
+    .into_iter()
+    .map(Into::into)
+    .collect())
 }
 
-#[tracing::instrument(skip_all)]
-pub async fn load_last_update_time(db_pool: &PgPool) -> Result<chrono::DateTime<chrono::Utc>> {
-    Ok(
-        sqlx::query!("SELECT last_update_time FROM last_update_time WHERE id = 0;")
-            .fetch_one(db_pool)
-            .await?
-            .last_update_time
-            .ok_or(anyhow::anyhow!("last_update_time not found"))?,
+/// The last successful scrape of `source`, or the oldest one across the enabled
+/// sources, so that a failing feed doesn't look up to date. There is none while
+/// any of the sources has never succeeded.
+#[tracing::instrument(skip(db_pool, enabled))]
+pub async fn load_last_update_time(
+    db_pool: &PgPool,
+    enabled: &HashMap<String, SourceSettings>,
+    source: Option<PostSource>,
+) -> Result<chrono::DateTime<chrono::Utc>> {
+    let sources = match source {
+        Some(source) => vec![source],
+        None => enabled_sources(enabled)
+            .into_iter()
+            .map(|source| source.post_source)
+            .collect(),
+    };
+    sqlx::query_scalar!(
+        r#"SELECT CASE WHEN BOOL_AND(latest.last_success IS NOT NULL)
+            THEN MIN(latest.last_success)
+        END
+        FROM UNNEST($1::post_source[]) AS enabled (source)
+        LEFT JOIN (
+            SELECT source, MAX(finished_at) AS last_success
+            FROM source_runs
+            WHERE success
+            GROUP BY source
+        ) AS latest USING (source)"#,
+        sources as Vec<PostSource>
     )
+    .fetch_one(db_pool)
+    .await?
+    .ok_or(Error::NotFound)
 }
@@ -0,0 +1,260 @@
+use axum::{
+    http::{HeaderName, HeaderValue},
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+
+use crate::{Error, Result};
+use config_structs::PaginationSettings;
+
+const DEPRECATION: HeaderName = HeaderName::from_static("deprecation");
+
+/// Query parameters shared by the endpoints that list posts.
+#[derive(serde::Deserialize)]
+pub struct PageQuery {
+    cursor: Option<String>,
+    /// Offset based pagination, deprecated in favour of `cursor`.
+    page: Option<i64>,
+    limit: Option<i64>,
+    #[serde(default)]
+    order: Order,
+}
+
+/// How the posts are sorted, newest first either way.
+#[derive(serde::Deserialize, Default, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Order {
+    /// By the time the posts were scraped.
+    #[default]
+    Id,
+    /// By the time the posts were published.
+    Created,
+}
+
+/// The position of a row in a listing, the next page starts after the last row.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Cursor {
+    pub id: i32,
+    /// Only used when ordering by [`Order::Created`].
+    pub created: Option<DateTime<Utc>>,
+}
+
+impl From<i32> for Cursor {
+    fn from(id: i32) -> Self {
+        Self { id, created: None }
+    }
+}
+
+/// Bounds of a single page, resolved from [`PageQuery`].
+pub struct Page {
+    /// Only rows with an `id` lower than this are returned, or, when ordering
+    /// by creation time, the rows that come after `(before_created, before_id)`.
+    pub before_id: Option<i32>,
+    pub before_created: Option<DateTime<Utc>>,
+    pub order: Order,
+    /// The number of rows to fetch, one more than the page size when paginating
+    /// with a cursor, so that the presence of a next page is known.
+    pub limit: i64,
+    pub offset: i64,
+    legacy: bool,
+}
+
+#[derive(serde::Serialize)]
+pub struct PostsPage<T> {
+    pub posts: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+impl PageQuery {
+    pub fn resolve(&self, settings: &PaginationSettings) -> Result<Page> {
+        let page_size = match self.limit {
+            Some(limit) if limit < 1 => {
+                return Err(Error::unprocessable_entity([(
+                    "limit",
+                    "must be a positive number",
+                )]))
+            }
+            Some(limit) => limit.min(settings.max_page_size),
+            None => settings.default_page_size,
+        };
+
+        match (&self.cursor, self.page) {
+            (Some(_), Some(_)) => Err(Error::unprocessable_entity([(
+                "cursor",
+                "can't be combined with page",
+            )])),
+            (_, Some(page)) if page < 0 => Err(Error::unprocessable_entity([(
+                "page",
+                "must not be negative",
+            )])),
+            (_, Some(page)) => Ok(Page {
+                before_id: None,
+                before_created: None,
+                order: self.order,
+                limit: page_size,
+                offset: page
+                    .checked_mul(page_size)
+                    .ok_or_else(|| Error::unprocessable_entity([("page", "is too large")]))?,
+                legacy: true,
+            }),
+            (cursor, None) => {
+                let cursor = cursor
+                    .as_deref()
+                    .map(|cursor| decode_cursor(cursor, self.order))
+                    .transpose()?;
+                Ok(Page {
+                    before_id: cursor.map(|c| c.id),
+                    before_created: cursor.and_then(|c| c.created),
+                    order: self.order,
+                    limit: page_size + 1,
+                    offset: 0,
+                    legacy: false,
+                })
+            }
+        }
+    }
+}
+
+impl Page {
+    pub fn by_created(&self) -> bool {
+        self.order == Order::Created
+    }
+
+    /// Builds the response out of `(cursor, item)` rows fetched with this page's bounds.
+    ///
+    /// Requests made with the deprecated `page` parameter get a bare list, as before.
+    pub fn into_response<K: Into<Cursor>, T: serde::Serialize>(
+        self,
+        rows: Vec<(K, T)>,
+    ) -> Response {
+        if self.legacy {
+            let (posts, _) = self.split(rows);
+            return (
+                [(DEPRECATION, HeaderValue::from_static("true"))],
+                Json(posts),
+            )
+                .into_response();
+        }
+
+        let (posts, next_cursor) = self.split(rows);
+        Json(PostsPage { posts, next_cursor }).into_response()
+    }
+
+    /// Splits `(cursor, item)` rows fetched with this page's bounds into the items
+    /// of the page and the cursor of the next one.
+    pub fn split<K: Into<Cursor>, T>(&self, rows: Vec<(K, T)>) -> (Vec<T>, Option<String>) {
+        let mut rows: Vec<(Cursor, T)> = rows.into_iter().map(|(k, t)| (k.into(), t)).collect();
+        let next_cursor = if !self.legacy && rows.len() as i64 == self.limit {
+            rows.pop();
+            rows.last()
+                .map(|(cursor, _)| encode_cursor(*cursor, self.order))
+        } else {
+            None
+        };
+        (
+            rows.into_iter().map(|(_, item)| item).collect(),
+            next_cursor,
+        )
+    }
+}
+
+fn encode_cursor(cursor: Cursor, order: Order) -> String {
+    let encoded = match (order, cursor.created) {
+        (Order::Created, Some(created)) => {
+            format!("created:{}:{}", created.timestamp_micros(), cursor.id)
+        }
+        _ => format!("id:{}", cursor.id),
+    };
+    URL_SAFE_NO_PAD.encode(encoded)
+}
+
+fn decode_cursor(cursor: &str, order: Order) -> Result<Cursor> {
+    let invalid = || Error::unprocessable_entity([("cursor", "is invalid")]);
+    let decoded = URL_SAFE_NO_PAD
+        .decode(cursor)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or_else(invalid)?;
+    match order {
+        Order::Id => decoded
+            .strip_prefix("id:")
+            .and_then(|id| id.parse::<i32>().ok())
+            .map(Cursor::from),
+        Order::Created => decoded
+            .strip_prefix("created:")
+            .and_then(|rest| rest.split_once(':'))
+            .and_then(|(created, id)| {
+                Some(Cursor {
+                    id: id.parse().ok()?,
+                    created: Some(DateTime::from_timestamp_micros(created.parse().ok()?)?),
+                })
+            }),
+    }
+    .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETTINGS: PaginationSettings = PaginationSettings {
+        default_page_size: 20,
+        max_page_size: 100,
+    };
+
+    fn query(cursor: Option<&str>, page: Option<i64>, limit: Option<i64>) -> PageQuery {
+        PageQuery {
+            cursor: cursor.map(str::to_owned),
+            page,
+            limit,
+            order: Order::Id,
+        }
+    }
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        let page = query(Some(&encode_cursor(42.into(), Order::Id)), None, None)
+            .resolve(&SETTINGS)
+            .unwrap();
+        assert_eq!(page.before_id, Some(42));
+        assert_eq!(page.limit, 21);
+        assert!(query(Some("garbage"), None, None)
+            .resolve(&SETTINGS)
+            .is_err());
+    }
+
+    #[test]
+    fn test_created_cursor_roundtrip() {
+        let cursor = Cursor {
+            id: 42,
+            created: DateTime::from_timestamp(1_790_000_000, 123_456_000),
+        };
+        let encoded = encode_cursor(cursor, Order::Created);
+        assert_eq!(decode_cursor(&encoded, Order::Created).unwrap(), cursor);
+        // A cursor only works with the order it was made for.
+        assert!(decode_cursor(&encoded, Order::Id).is_err());
+        assert!(decode_cursor(&encode_cursor(42.into(), Order::Id), Order::Created).is_err());
+    }
+
+    #[test]
+    fn test_page_size_is_bounded() {
+        let page = query(None, None, Some(1000)).resolve(&SETTINGS).unwrap();
+        assert_eq!(page.limit, 101);
+        assert!(query(None, None, Some(0)).resolve(&SETTINGS).is_err());
+    }
+
+    #[test]
+    fn test_legacy_page_uses_offset() {
+        let page = query(None, Some(2), None).resolve(&SETTINGS).unwrap();
+        assert_eq!((page.before_id, page.limit, page.offset), (None, 20, 40));
+    }
+
+    #[test]
+    fn test_huge_page_is_rejected() {
+        assert!(query(None, Some(i64::MAX), None)
+            .resolve(&SETTINGS)
+            .is_err());
+    }
+}
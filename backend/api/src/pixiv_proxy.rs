@@ -0,0 +1,220 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, State},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_NONE_MATCH, REFERER},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::StreamExt;
+use reqwest::Client;
+
+use crate::{image_cache::ImageCache, Error, Result};
+use config_structs::ApiState;
+
+const PIXIV_REFERER: &str = "https://www.pixiv.net/";
+const CACHE_CONTROL_VALUE: &str = "public, max-age=604800, immutable";
+
+#[derive(serde::Deserialize)]
+struct DetailsResponse {
+    body: DetailsBody,
+}
+
+#[derive(serde::Deserialize)]
+struct DetailsBody {
+    illust_details: IllustDetails,
+}
+
+#[derive(serde::Deserialize)]
+struct IllustDetails {
+    url: String,
+    page_count: String,
+}
+
+#[derive(serde::Deserialize)]
+struct UgoiraResponse {
+    body: UgoiraMeta,
+}
+
+/// Where to download the frames of an ugoira and how long to show each of them.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub(crate) struct UgoiraMeta {
+    /// A zip of the frames, fetching it needs a Pixiv `Referer`.
+    src: String,
+    #[serde(rename(deserialize = "originalSrc"))]
+    original_src: String,
+    mime_type: String,
+    frames: Vec<UgoiraFrame>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct UgoiraFrame {
+    file: String,
+    /// In milliseconds.
+    delay: u32,
+}
+
+/// Proxies a Pixiv image, since i.pximg.net refuses requests without a Pixiv `Referer`.
+#[tracing::instrument(skip(api_client, sources_urls, cache, headers))]
+pub(crate) async fn pixiv_image(
+    State(ApiState {
+        api_client,
+        sources_urls,
+        ..
+    }): State<ApiState>,
+    State(cache): State<ImageCache>,
+    Path((illust_id, page)): Path<(String, u32)>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    if illust_id.is_empty() || !illust_id.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(Error::PixivId);
+    }
+    let key = format!("{illust_id}_p{page}");
+    let etag = format!("\"pixiv-{key}\"");
+
+    let if_none_match = headers.get(IF_NONE_MATCH).and_then(|v| v.to_str().ok());
+    if if_none_match == Some(etag.as_str()) {
+        return Ok((StatusCode::NOT_MODIFIED, cached_headers(&etag, None)).into_response());
+    }
+
+    if let Some(bytes) = cache.get(&key).await {
+        return Ok((cached_headers(&etag, sniff_content_type(&bytes)), bytes).into_response());
+    }
+
+    let details_url = format!("{}{}", sources_urls.pixiv_details, illust_id);
+    let details = request(&api_client, &details_url)
+        .await?
+        .json::<DetailsResponse>()
+        .await?
+        .body
+        .illust_details;
+    let page_count: u32 = details.page_count.parse().map_err(|_| Error::Parsing)?;
+    if page >= page_count {
+        return Err(Error::NotFound);
+    }
+    let image_url =
+        image_url(&sources_urls.pixiv_image, &details.url, page).ok_or(Error::Parsing)?;
+
+    let upstream = request(&api_client, &image_url).await?;
+    let content_type = upstream
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned);
+
+    // Stream the image to the client while collecting it for the cache.
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Bytes, std::io::Error>>(16);
+    tokio::spawn(async move {
+        let mut stream = upstream.bytes_stream();
+        let mut buffer = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(chunk) => {
+                    buffer.extend_from_slice(&chunk);
+                    // The client may have gone away, keep downloading for the cache anyway.
+                    let _ = tx.send(Ok(chunk)).await;
+                }
+                Err(e) => {
+                    tracing::error!("Failed to read a Pixiv image {}: {:?}", key, e);
+                    let _ = tx.send(Err(std::io::Error::other(e))).await;
+                    return;
+                }
+            }
+        }
+        if let Err(e) = cache.insert(&key, &buffer).await {
+            tracing::warn!("Failed to cache a Pixiv image {}: {:?}", key, e);
+        }
+    });
+    let body = Body::from_stream(futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    }));
+
+    Ok((cached_headers(&etag, content_type.as_deref()), body).into_response())
+}
+
+/// Returns the frame zip metadata of a Pixiv ugoira.
+#[tracing::instrument(skip(api_client, sources_urls))]
+pub(crate) async fn pixiv_ugoira(
+    State(ApiState {
+        api_client,
+        sources_urls,
+        ..
+    }): State<ApiState>,
+    Path(illust_id): Path<String>,
+) -> Result<Response> {
+    if illust_id.is_empty() || !illust_id.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(Error::PixivId);
+    }
+    let meta_url = format!("{}{}/ugoira_meta", sources_urls.pixiv_ugoira, illust_id);
+    let meta = request(&api_client, &meta_url)
+        .await?
+        .json::<UgoiraResponse>()
+        .await?
+        .body;
+    Ok(([(CACHE_CONTROL, CACHE_CONTROL_VALUE)], Json(meta)).into_response())
+}
+
+async fn request(client: &Client, url: &str) -> Result<reqwest::Response> {
+    let response = client
+        .get(url)
+        .header(REFERER, PIXIV_REFERER)
+        .send()
+        .await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(Error::NotFound);
+    }
+    Ok(response.error_for_status()?)
+}
+
+fn cached_headers(etag: &str, content_type: Option<&str>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static(CACHE_CONTROL_VALUE));
+    if let Ok(etag) = HeaderValue::from_str(etag) {
+        headers.insert(ETAG, etag);
+    }
+    if let Some(content_type) = content_type.and_then(|c| HeaderValue::from_str(c).ok()) {
+        headers.insert(CONTENT_TYPE, content_type);
+    }
+    headers
+}
+
+/// Builds the full size image URL for `page` from the thumbnail URL returned by
+/// the details API, e.g. `https://i.pximg.net/c/540x540_70/img-master/img/2023/03/17/22/01/14/106302935_p0_master1200.jpg`.
+fn image_url(pixiv_image: &str, details_url: &str, page: u32) -> Option<String> {
+    let (_, path) = details_url.split_once("/img-master")?;
+    let (dir, file) = path.rsplit_once('/')?;
+    let (illust_id, rest) = file.split_once("_p0")?;
+    Some(format!("{pixiv_image}{dir}/{illust_id}_p{page}{rest}"))
+}
+
+fn sniff_content_type(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_url() {
+        let details_url = "https://i.pximg.net/c/540x540_70/img-master/img/2023/03/17/22/01/14/106302935_p0_master1200.jpg";
+        assert_eq!(
+            image_url("https://i.pximg.net/img-master", details_url, 2).unwrap(),
+            "https://i.pximg.net/img-master/img/2023/03/17/22/01/14/106302935_p2_master1200.jpg"
+        );
+        assert!(image_url(
+            "https://i.pximg.net/img-master",
+            "https://example.com/a.jpg",
+            0
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,203 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+
+use crate::{
+    auth::Admin,
+    pagination::{Page, PageQuery},
+    Error, Result,
+};
+use config_structs::ApiState;
+use etl::{
+    runs::{request_run, RequestedRun, ScrapeStatus},
+    PostSource,
+};
+
+/// A scrape run with the counts of all its sources added up.
+#[derive(serde::Serialize)]
+pub(crate) struct ScrapeRun {
+    id: i32,
+    status: ScrapeStatus,
+    /// The requested sources, `None` when every source is scraped.
+    sources: Option<Vec<PostSource>>,
+    requested_at: DateTime<Utc>,
+    started_at: Option<DateTime<Utc>>,
+    finished_at: Option<DateTime<Utc>>,
+    error: Option<String>,
+    fetched: i64,
+    new: i64,
+    updated: i64,
+    duplicate: i64,
+    blacklisted: i64,
+    failed_sources: i64,
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct SourceRun {
+    source: PostSource,
+    started_at: DateTime<Utc>,
+    finished_at: DateTime<Utc>,
+    success: bool,
+    error_kind: Option<String>,
+    error: Option<String>,
+    duration_ms: i32,
+    fetched_count: i32,
+    new_count: i32,
+    updated_count: i32,
+    duplicate_count: i32,
+    blacklisted_count: i32,
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct RunsPage {
+    runs: Vec<ScrapeRun>,
+    next_cursor: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct ScrapeRunDetails {
+    #[serde(flatten)]
+    run: ScrapeRun,
+    source_runs: Vec<SourceRun>,
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct UpdateRequest {
+    /// Every source is scraped when omitted.
+    sources: Option<Vec<PostSource>>,
+}
+
+/// Asks the scraper to run now, returning the run that will cover the request.
+#[tracing::instrument(skip_all)]
+pub(crate) async fn request_update(
+    _: Admin,
+    State(ApiState { db_pool, .. }): State<ApiState>,
+    request: Option<Json<UpdateRequest>>,
+) -> Result<(StatusCode, Json<RequestedRun>)> {
+    let sources = request.and_then(|Json(request)| request.sources);
+    if sources.as_ref().is_some_and(Vec::is_empty) {
+        return Err(Error::unprocessable_entity([(
+            "sources",
+            "must not be empty",
+        )]));
+    }
+    let run = request_run(&db_pool, sources.as_deref()).await?;
+    Ok((StatusCode::ACCEPTED, Json(run)))
+}
+
+/// Lists the runs newest first. Restricted to the admin as the errors of the
+/// sources are reported verbatim.
+#[tracing::instrument(skip_all)]
+pub(crate) async fn list_runs(
+    _: Admin,
+    State(ApiState {
+        db_pool,
+        pagination,
+        ..
+    }): State<ApiState>,
+    Query(page_query): Query<PageQuery>,
+) -> Result<Json<RunsPage>> {
+    let page: Page = page_query.resolve(&pagination)?;
+    // The runs have no publication time to sort on.
+    if page.by_created() {
+        return Err(Error::unprocessable_entity([(
+            "order",
+            "runs can only be ordered by id",
+        )]));
+    }
+    let rows = sqlx::query_as!(
+        ScrapeRun,
+        r#"SELECT
+            r.id,
+            r.status AS "status: ScrapeStatus",
+            r.sources AS "sources: Vec<PostSource>",
+            r.requested_at,
+            r.started_at,
+            r.finished_at,
+            r.error,
+            COALESCE(SUM(s.fetched_count), 0) AS "fetched!",
+            COALESCE(SUM(s.new_count), 0) AS "new!",
+            COALESCE(SUM(s.updated_count), 0) AS "updated!",
+            COALESCE(SUM(s.duplicate_count), 0) AS "duplicate!",
+            COALESCE(SUM(s.blacklisted_count), 0) AS "blacklisted!",
+            COUNT(s.id) FILTER (WHERE NOT s.success) AS "failed_sources!"
+        FROM scrape_runs r
+        LEFT JOIN source_runs s ON s.run_id = r.id
+        WHERE $1::INT IS NULL OR r.id < $1
+        GROUP BY r.id
+        ORDER BY r.id DESC
+        LIMIT $2 OFFSET $3"#,
+        page.before_id,
+        page.limit,
+        page.offset
+    )
+    .fetch_all(&db_pool)
+    .await?
+    .into_iter()
+    .map(|run| (run.id, run))
+    .collect();
+
+    let (runs, next_cursor) = page.split(rows);
+    Ok(Json(RunsPage { runs, next_cursor }))
+}
+
+#[tracing::instrument(skip(db_pool))]
+pub(crate) async fn get_run(
+    _: Admin,
+    State(ApiState { db_pool, .. }): State<ApiState>,
+    Path(id): Path<i32>,
+) -> Result<Json<ScrapeRunDetails>> {
+    let run = sqlx::query_as!(
+        ScrapeRun,
+        r#"SELECT
+            r.id,
+            r.status AS "status: ScrapeStatus",
+            r.sources AS "sources: Vec<PostSource>",
+            r.requested_at,
+            r.started_at,
+            r.finished_at,
+            r.error,
+            COALESCE(SUM(s.fetched_count), 0) AS "fetched!",
+            COALESCE(SUM(s.new_count), 0) AS "new!",
+            COALESCE(SUM(s.updated_count), 0) AS "updated!",
+            COALESCE(SUM(s.duplicate_count), 0) AS "duplicate!",
+            COALESCE(SUM(s.blacklisted_count), 0) AS "blacklisted!",
+            COUNT(s.id) FILTER (WHERE NOT s.success) AS "failed_sources!"
+        FROM scrape_runs r
+        LEFT JOIN source_runs s ON s.run_id = r.id
+        WHERE r.id = $1
+        GROUP BY r.id"#,
+        id
+    )
+    .fetch_optional(&db_pool)
+    .await?
+    .ok_or(Error::NotFound)?;
+
+    let source_runs = sqlx::query_as!(
+        SourceRun,
+        r#"SELECT
+            source AS "source!: PostSource",
+            started_at,
+            finished_at,
+            success,
+            error_kind,
+            error,
+            duration_ms,
+            fetched_count,
+            new_count,
+            updated_count,
+            duplicate_count,
+            blacklisted_count
+        FROM source_runs
+        WHERE run_id = $1
+        ORDER BY id"#,
+        id
+    )
+    .fetch_all(&db_pool)
+    .await?;
+
+    Ok(Json(ScrapeRunDetails { run, source_runs }))
+}
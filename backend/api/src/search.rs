@@ -0,0 +1,242 @@
+use axum::{
+    extract::{Query, State},
+    response::Response,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::types::Json;
+
+use crate::{
+    blocklist::UserKey,
+    load::{FeedPost, PostRow},
+    pagination::{Cursor, Page, PageQuery},
+    Error, Result,
+};
+use config_structs::ApiState;
+use etl::{MediaKind, PostImage, PostSource};
+
+#[derive(serde::Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMode {
+    #[default]
+    Any,
+    All,
+}
+
+#[derive(serde::Deserialize)]
+pub struct SearchQuery {
+    /// Comma separated list of tags.
+    tags: Option<String>,
+    #[serde(default)]
+    tag_mode: TagMode,
+    author: Option<String>,
+    source: Option<PostSource>,
+    /// RFC 3339 timestamp or a `YYYY-MM-DD` date.
+    since: Option<String>,
+    /// RFC 3339 timestamp or a `YYYY-MM-DD` date, which includes the whole day.
+    until: Option<String>,
+    /// Free text matched against the author and the tags, every word as a
+    /// substring.
+    q: Option<String>,
+}
+
+struct Filters {
+    tags: Option<Vec<String>>,
+    all_tags: bool,
+    author: Option<String>,
+    source: Option<PostSource>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    /// `ILIKE` patterns that must all match.
+    q: Option<Vec<String>>,
+}
+
+impl TryFrom<SearchQuery> for Filters {
+    type Error = Error;
+
+    fn try_from(query: SearchQuery) -> Result<Self> {
+        let tags = query.tags.map(|tags| {
+            tags.split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_owned)
+                .collect::<Vec<_>>()
+        });
+        Ok(Self {
+            tags: tags.filter(|tags| !tags.is_empty()),
+            all_tags: matches!(query.tag_mode, TagMode::All),
+            author: query.author.filter(|a| !a.is_empty()),
+            source: query.source,
+            since: query
+                .since
+                .as_deref()
+                .map(parse_date("since", false))
+                .transpose()?,
+            until: query
+                .until
+                .as_deref()
+                .map(parse_date("until", true))
+                .transpose()?,
+            q: query
+                .q
+                .as_deref()
+                .map(substring_patterns)
+                .filter(|q| !q.is_empty()),
+        })
+    }
+}
+
+/// A date alone stands for its midnight, or for the next one when it ends a
+/// range so that the day is included.
+fn parse_date(field: &'static str, end: bool) -> impl Fn(&str) -> Result<DateTime<Utc>> {
+    move |value| {
+        DateTime::parse_from_rfc3339(value)
+            .map(|date| date.with_timezone(&Utc))
+            .ok()
+            .or_else(|| {
+                let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+                // The last representable date has no next midnight.
+                let date = if end { date.succ_opt()? } else { date };
+                Some(date.and_hms_opt(0, 0, 0)?.and_utc())
+            })
+            .ok_or_else(|| {
+                Error::unprocessable_entity([(field, "must be an RFC 3339 timestamp or a date")])
+            })
+    }
+}
+
+/// Escapes the `LIKE` wildcards of each word.
+fn substring_patterns(q: &str) -> Vec<String> {
+    q.split_whitespace()
+        .map(|word| {
+            let word = word
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            format!("%{word}%")
+        })
+        .collect()
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn search_posts(
+    State(ApiState {
+        db_pool,
+        pagination,
+        ..
+    }): State<ApiState>,
+    user_key: Option<UserKey>,
+    Query(page_query): Query<PageQuery>,
+    Query(search_query): Query<SearchQuery>,
+) -> Result<Response> {
+    let page: Page = page_query.resolve(&pagination)?;
+    let filters = Filters::try_from(search_query)?;
+
+    let posts: Vec<(Cursor, FeedPost)> = sqlx::query_as!(
+        PostRow,
+        r#"SELECT
+            id,
+            post_link,
+            preview_link,
+            images_number,
+            media_kind AS "media_kind!: MediaKind",
+            created,
+            author,
+            author_link,
+            source AS "source!: PostSource",
+            tags,
+            author_profile_image,
+            COALESCE(
+                (SELECT JSONB_AGG(
+                    JSONB_BUILD_OBJECT(
+                        'url', url, 'width', width, 'height', height, 'variants', variants
+                    )
+                    ORDER BY position
+                ) FROM post_images WHERE post_id = honkai_posts.id),
+                '[]'
+            ) AS "images!: Json<Vec<PostImage>>",
+            ARRAY(
+                SELECT d.post_link FROM honkai_posts d
+                WHERE d.duplicate_of = honkai_posts.id AND d.hidden_at IS NULL
+                ORDER BY d.id
+            ) AS "alternates!"
+        FROM honkai_posts
+        WHERE hidden_at IS NULL
+            AND duplicate_of IS NULL
+            AND ($1::TEXT[] IS NULL OR CASE WHEN $2 THEN tags @> $1 ELSE tags && $1 END)
+            AND ($3::TEXT IS NULL OR author = $3)
+            AND ($4::post_source IS NULL OR source = $4)
+            AND ($5::TIMESTAMPTZ IS NULL OR created >= $5)
+            AND ($6::TIMESTAMPTZ IS NULL OR created < $6)
+            AND ($7::TEXT[] IS NULL OR honkai_posts_search_text(author, tags) ILIKE ALL ($7))
+            AND ($8::INT IS NULL
+                OR CASE WHEN $12 THEN (created, id) < ($13, $8) ELSE id < $8 END)
+            AND NOT EXISTS (
+                SELECT 1 FROM user_blocklist b
+                WHERE b.user_key = $11
+                    AND ((b.kind = 'author' AND b.value = honkai_posts.author)
+                        OR (b.kind = 'tag' AND b.value = ANY(honkai_posts.tags)))
+            )
+        ORDER BY CASE WHEN $12 THEN created END DESC, id DESC
+        LIMIT $9 OFFSET $10"#,
+        filters.tags.as_deref(),
+        filters.all_tags,
+        filters.author,
+        filters.source as Option<PostSource>,
+        filters.since,
+        filters.until,
+        filters.q.as_deref(),
+        page.before_id,
+        page.limit,
+        page.offset,
+        user_key.map(|k| k.0),
+        page.by_created(),
+        page.before_created
+    )
+    .fetch_all(&db_pool)
+    .await?
+    .into_iter()
+    .map(Into::into)
+    .collect();
+
+    Ok(page.into_response(posts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date() {
+        let parse = parse_date("since", false);
+        assert_eq!(
+            parse("2026-09-01").unwrap().to_rfc3339(),
+            "2026-09-01T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse("2026-09-01T12:00:00+03:00").unwrap().to_rfc3339(),
+            "2026-09-01T09:00:00+00:00"
+        );
+        assert!(parse("yesterday").is_err());
+
+        let parse = parse_date("until", true);
+        assert_eq!(
+            parse("2026-09-30").unwrap().to_rfc3339(),
+            "2026-10-01T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse("2026-09-01T12:00:00+00:00").unwrap().to_rfc3339(),
+            "2026-09-01T12:00:00+00:00"
+        );
+        let last_date = NaiveDate::MAX.to_string();
+        assert!(parse_date("since", false)(&last_date).is_ok());
+        assert!(parse(&last_date).is_err());
+    }
+
+    #[test]
+    fn test_substring_patterns() {
+        assert_eq!(
+            substring_patterns(" 崩坏  100%_off "),
+            ["%崩坏%", "%100\\%\\_off%"]
+        );
+    }
+}
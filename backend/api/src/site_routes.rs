@@ -2,9 +2,22 @@ use axum::extract::{Query, State};
 
 use crate::{load::load_last_update_time, Error};
 use config_structs::ApiState;
+use etl::PostSource;
 
-pub(crate) async fn last_update(State(state): State<ApiState>) -> Result<String, Error> {
-    Ok(load_last_update_time(&state.db_pool).await?.to_rfc3339())
+#[derive(serde::Deserialize)]
+pub(crate) struct LastUpdateQuery {
+    source: Option<PostSource>,
+}
+
+pub(crate) async fn last_update(
+    State(state): State<ApiState>,
+    Query(LastUpdateQuery { source }): Query<LastUpdateQuery>,
+) -> Result<String, Error> {
+    Ok(
+        load_last_update_time(&state.db_pool, &state.sources, source)
+            .await?
+            .to_rfc3339(),
+    )
 }
 
 #[derive(serde::Deserialize)]
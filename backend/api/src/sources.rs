@@ -0,0 +1,55 @@
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+
+use crate::Result;
+use config_structs::ApiState;
+use etl::PostSource;
+
+/// The latest run of a source, along with the time it last succeeded.
+#[derive(serde::Serialize)]
+pub struct SourceStatus {
+    source: PostSource,
+    last_run_at: DateTime<Utc>,
+    success: bool,
+    error_kind: Option<String>,
+    error: Option<String>,
+    fetched_count: i32,
+    new_count: i32,
+    updated_count: i32,
+    duplicate_count: i32,
+    blacklisted_count: i32,
+    duration_ms: i32,
+    last_success_at: Option<DateTime<Utc>>,
+}
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn sources_status(
+    State(ApiState { db_pool, .. }): State<ApiState>,
+) -> Result<Json<Vec<SourceStatus>>> {
+    let statuses = sqlx::query_as!(
+        SourceStatus,
+        r#"SELECT DISTINCT ON (source)
+            source AS "source!: PostSource",
+            finished_at AS last_run_at,
+            success,
+            error_kind,
+            error,
+            fetched_count,
+            new_count,
+            updated_count,
+            duplicate_count,
+            blacklisted_count,
+            duration_ms,
+            (
+                SELECT MAX(s.finished_at)
+                FROM source_runs s
+                WHERE s.source = r.source AND s.success
+            ) AS last_success_at
+        FROM source_runs r
+        ORDER BY source, finished_at DESC"#
+    )
+    .fetch_all(&db_pool)
+    .await?;
+
+    Ok(Json(statuses))
+}
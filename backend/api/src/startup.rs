@@ -1,6 +1,10 @@
 use std::{sync::Arc, time::Duration};
 
-use axum::{extract::FromRef, routing::get, Router};
+use axum::{
+    extract::FromRef,
+    routing::{get, post, put},
+    Router,
+};
 
 use reqwest::{header, Client};
 use secrecy::ExposeSecret;
@@ -9,18 +13,37 @@ use tokio::net::TcpListener;
 use tower_http::services::{ServeDir, ServeFile};
 
 use crate::{
+    blacklist::{
+        create_blacklist_entry, delete_blacklist_entry, list_blacklist, purge_blacklist,
+        update_blacklist_entry,
+    },
+    blocklist::{block, get_blocklist, unblock},
+    image_cache::ImageCache,
     load::{load_honkai_posts, load_twitter_home_posts},
+    pixiv_proxy::{pixiv_image, pixiv_ugoira},
+    runs::{get_run, list_runs, request_update},
+    search::search_posts,
     site_routes::{last_update, like},
+    sources::sources_status,
     Error,
 };
 use config_structs::{ApiState, DatabaseSettings, Settings};
 
 #[derive(Clone)]
-pub struct StateWrapper(Arc<ApiState>);
+pub struct StateWrapper {
+    api: Arc<ApiState>,
+    image_cache: ImageCache,
+}
 
 impl FromRef<StateWrapper> for ApiState {
     fn from_ref(wrapper: &StateWrapper) -> ApiState {
-        wrapper.0.clone().into()
+        wrapper.api.clone().into()
+    }
+}
+
+impl FromRef<StateWrapper> for ImageCache {
+    fn from_ref(wrapper: &StateWrapper) -> ImageCache {
+        wrapper.image_cache.clone()
     }
 }
 
@@ -82,9 +105,29 @@ impl Application {
 
         Router::new()
             .route("/api/like", get(like))
+            .route("/api/update", post(request_update))
             .route("/api/update/last_update", get(last_update))
             .route("/api/honkai", get(load_honkai_posts))
             .route("/api/myfeed", get(load_twitter_home_posts))
+            .route("/api/pixiv/:illust_id/:page", get(pixiv_image))
+            .route("/api/pixiv/:illust_id/ugoira", get(pixiv_ugoira))
+            .route("/api/search", get(search_posts))
+            .route("/api/sources/status", get(sources_status))
+            .route("/api/runs", get(list_runs))
+            .route("/api/runs/:id", get(get_run))
+            .route(
+                "/api/blocklist",
+                get(get_blocklist).post(block).delete(unblock),
+            )
+            .route(
+                "/api/admin/blacklist",
+                get(list_blacklist).post(create_blacklist_entry),
+            )
+            .route("/api/admin/blacklist/purge", post(purge_blacklist))
+            .route(
+                "/api/admin/blacklist/:id",
+                put(update_blacklist_entry).delete(delete_blacklist_entry),
+            )
             .nest_service("/", serve_dir.clone())
             .fallback_service(serve_dir)
             .layer(tele::opentelemetry_tracing_layer())
@@ -107,8 +150,19 @@ impl Application {
             db_pool,
             api_client: Self::create_api_client(&config).expect("Failed to create the api client"),
             sources_urls: config.app.sources_urls,
+            pagination: config.app.pagination,
+            admin_token: config.app.admin_token,
+            blacklist: config.app.blacklist,
+            sources: config.app.sources,
         });
-        let router = Self::create_router(StateWrapper(state)).await;
+        let image_cache = ImageCache::open(&config.app.image_cache)
+            .await
+            .expect("Failed to open the image cache");
+        let router = Self::create_router(StateWrapper {
+            api: state,
+            image_cache,
+        })
+        .await;
 
         Self {
             port,
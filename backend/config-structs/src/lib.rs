@@ -1,18 +1,61 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use reqwest::Client;
 use secrecy::{ExposeSecret, Secret};
 use serde_aux::prelude::deserialize_number_from_string;
 use sqlx::{postgres::PgConnectOptions, ConnectOptions, PgPool};
 
+/// URLs requested by the api, the scraped ones are in [`SourceSettings`].
 #[derive(serde::Deserialize, Clone)]
 pub struct SourcesUrls {
-    pub pixiv: String,
     pub pixiv_details: String,
     pub pixiv_image: String,
-    pub twitter_honkai: String,
-    pub twitter_home: String,
-    pub mihoyo: String,
+    /// Followed by `{illust_id}/ugoira_meta`.
+    pub pixiv_ugoira: String,
+}
+
+/// Settings of a single source, keyed by the source id in [`ApplicationSettings::sources`].
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct SourceSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub url: String,
+    /// Cron expression with seconds, e.g. `"0 */20 * * * *"`.
+    pub cron: String,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub timeout_secs: u64,
+    /// How many pages to follow before giving up on reaching an already stored post.
+    #[serde(
+        default = "default_max_pages",
+        deserialize_with = "deserialize_number_from_string"
+    )]
+    pub max_pages: u32,
+    /// Pause between two pages of the same source.
+    #[serde(default, deserialize_with = "deserialize_number_from_string")]
+    pub page_delay_ms: u64,
+    /// The boards to follow instead of the one in `url`, for the sources built
+    /// on the Mihoyo forum API.
+    #[serde(default)]
+    pub forums: Vec<ForumSettings>,
+}
+
+/// A board of a Mihoyo forum, e.g. `forum_id: 4` and `gids: 1` for the
+/// Honkai Impact 3rd fan art.
+#[derive(serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ForumSettings {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub forum_id: u32,
+    /// The id of the game.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub gids: u32,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_max_pages() -> u32 {
+    1
 }
 
 #[derive(serde::Deserialize, Clone)]
@@ -26,7 +69,8 @@ pub struct ScraperState {
     pub db_pool: PgPool,
     pub api_client: Client,
     pub blacklist: BlackList,
-    pub sources_urls: SourcesUrls,
+    pub sources: HashMap<String, SourceSettings>,
+    pub dedup: DedupSettings,
 }
 
 impl From<Arc<ScraperState>> for ScraperState {
@@ -40,6 +84,10 @@ pub struct ApiState {
     pub db_pool: PgPool,
     pub api_client: Client,
     pub sources_urls: SourcesUrls,
+    pub pagination: PaginationSettings,
+    pub admin_token: Option<Secret<String>>,
+    pub blacklist: BlackList,
+    pub sources: HashMap<String, SourceSettings>,
 }
 
 impl From<Arc<ApiState>> for ApiState {
@@ -68,7 +116,45 @@ pub struct ApplicationSettings {
     pub host: String,
     pub blacklist: BlackList,
     pub sources_urls: SourcesUrls,
+    pub sources: HashMap<String, SourceSettings>,
     pub headers: ApiClientHeaders,
+    pub image_cache: ImageCacheSettings,
+    pub pagination: PaginationSettings,
+    pub dedup: DedupSettings,
+    /// Token for the admin endpoints of the api, they are disabled when it's not set.
+    pub admin_token: Option<Secret<String>>,
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct PaginationSettings {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub default_page_size: i64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_page_size: i64,
+}
+
+/// Grouping of the same artwork posted on several sources.
+#[derive(serde::Deserialize, Clone)]
+pub struct DedupSettings {
+    /// When to hash the previews of the new posts.
+    pub cron: String,
+    /// How many previews to hash at a time.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub batch_size: i64,
+    /// How many bits two preview hashes may differ by to be considered the same artwork.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_distance: i64,
+    /// How many days apart the copies of an artwork may be posted, only the posts
+    /// of this window are compared.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub window_days: i32,
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct ImageCacheSettings {
+    pub dir: String,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_size_mb: u64,
 }
 
 #[derive(serde::Deserialize, Clone)]
@@ -33,6 +33,9 @@ pub enum Error {
     #[error("failed to parse a response from a source API")]
     Parsing,
 
+    #[error("a source API didn't respond in time")]
+    Timeout,
+
     #[error("failed to parse the PixivId from the URL")]
     PixivId,
 
@@ -61,6 +64,25 @@ impl Error {
         Self::UnprocessableEntity { errors: error_map }
     }
 
+    /// A short machine readable name of the error, e.g. for the source health log.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Unauthorized => "unauthorized",
+            Self::Forbidden => "forbidden",
+            Self::NotFound => "not_found",
+            Self::UnprocessableEntity { .. } => "unprocessable_entity",
+            Self::Sqlx(_) => "database",
+            Self::Reqwest(e) if e.is_timeout() => "timeout",
+            Self::Reqwest(e) if e.is_status() => "status",
+            Self::Reqwest(e) if e.is_decode() => "decode",
+            Self::Reqwest(_) => "request",
+            Self::Parsing | Self::PixivId => "parsing",
+            Self::Timeout => "timeout",
+            Self::TaskError(_) => "task",
+            Self::Anyhow(_) => "internal",
+        }
+    }
+
     fn status_code(&self) -> StatusCode {
         match self {
             Self::Unauthorized => StatusCode::UNAUTHORIZED,
@@ -72,6 +94,7 @@ impl Error {
             | Self::Reqwest(_)
             | Self::Parsing
             | Self::TaskError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Timeout => StatusCode::GATEWAY_TIMEOUT,
         }
     }
 }
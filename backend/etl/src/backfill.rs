@@ -0,0 +1,179 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::PgPool;
+
+use crate::{
+    extract::{save_posts, PostCounts},
+    load::load_blacklist,
+    transform::{PostSource, SOURCES},
+    Error, Result,
+};
+use config_structs::ScraperState;
+
+/// The progress of a backfill, see [`backfill`].
+#[derive(serde::Serialize, Debug)]
+pub struct Backfill {
+    pub id: i32,
+    pub source: PostSource,
+    pub since: NaiveDate,
+    pub until: NaiveDate,
+    /// The page to request next, `None` once the backfill is finished.
+    pub next_url: Option<String>,
+    pub pages: i32,
+    pub fetched_count: i32,
+    pub new_count: i32,
+    pub updated_count: i32,
+    pub duplicate_count: i32,
+    pub blacklisted_count: i32,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Pages through the search of a source for the posts created from `since`
+/// to `until` inclusive and saves them like a regular run does.
+///
+/// The progress is stored after every page, so running the same backfill
+/// again resumes it where it was interrupted.
+#[tracing::instrument(skip(state))]
+pub async fn backfill(
+    state: &ScraperState,
+    source_id: &str,
+    since: NaiveDate,
+    until: NaiveDate,
+) -> Result<Backfill> {
+    let source = SOURCES
+        .iter()
+        .find(|source| source.id == source_id)
+        .ok_or_else(|| anyhow!("Unknown source {:?}", source_id))?;
+    let settings = state
+        .sources
+        .get(source.id)
+        .ok_or_else(|| anyhow!("The source {:?} isn't configured", source.id))?;
+    if since > until {
+        return Err(anyhow!("The window starts after it ends").into());
+    }
+    let url = source
+        .backfill_url(&settings.url, since, until)
+        .ok_or_else(|| anyhow!("The source {:?} can't search by date", source.id))?;
+
+    let mut backfill =
+        start_backfill(&state.db_pool, source.post_source, since, until, &url).await?;
+    if backfill.pages > 0 {
+        tracing::info!(
+            "Resuming backfill {} after {} pages",
+            backfill.id,
+            backfill.pages
+        );
+    }
+    let blacklist = load_blacklist(&state.db_pool, &state.blacklist).await?;
+    let timeout = Duration::from_secs(settings.timeout_secs);
+    while let Some(url) = backfill.next_url.take() {
+        let page = tokio::time::timeout(timeout, source.request(&state.api_client, &url))
+            .await
+            .unwrap_or(Err(Error::Timeout))?;
+        let counts = save_posts(&state.db_pool, &blacklist, &page.posts).await?;
+        // Twitter keeps returning a cursor past the last result.
+        let next_url = page.next_url.filter(|_| !page.posts.is_empty());
+        backfill = save_progress(&state.db_pool, backfill.id, next_url.as_deref(), &counts).await?;
+        tracing::info!(
+            "Backfill {}: page {}, {} new posts",
+            backfill.id,
+            backfill.pages,
+            counts.new
+        );
+        if backfill.next_url.is_some() {
+            tokio::time::sleep(Duration::from_millis(settings.page_delay_ms)).await;
+        }
+    }
+    Ok(backfill)
+}
+
+/// Creates the backfill of the window, or returns the unfinished one.
+async fn start_backfill(
+    db_pool: &PgPool,
+    source: PostSource,
+    since: NaiveDate,
+    until: NaiveDate,
+    url: &str,
+) -> Result<Backfill> {
+    let backfill = sqlx::query_as!(
+        Backfill,
+        r#"
+        INSERT INTO backfills (source, since, until, next_url)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (source, since, until) WHERE finished_at IS NULL
+        DO UPDATE SET updated_at = NOW()
+        RETURNING
+            id,
+            source AS "source: PostSource",
+            since,
+            until,
+            next_url,
+            pages,
+            fetched_count,
+            new_count,
+            updated_count,
+            duplicate_count,
+            blacklisted_count,
+            started_at,
+            finished_at
+        "#,
+        source as PostSource,
+        since,
+        until,
+        url
+    )
+    .fetch_one(db_pool)
+    .await?;
+    Ok(backfill)
+}
+
+async fn save_progress(
+    db_pool: &PgPool,
+    id: i32,
+    next_url: Option<&str>,
+    counts: &PostCounts,
+) -> Result<Backfill> {
+    let backfill = sqlx::query_as!(
+        Backfill,
+        r#"
+        UPDATE backfills SET
+            next_url = $2::TEXT,
+            pages = pages + 1,
+            fetched_count = fetched_count + $3,
+            new_count = new_count + $4,
+            updated_count = updated_count + $5,
+            duplicate_count = duplicate_count + $6,
+            blacklisted_count = blacklisted_count + $7,
+            updated_at = NOW(),
+            finished_at = CASE WHEN $2::TEXT IS NULL THEN NOW() END
+        WHERE id = $1
+        RETURNING
+            id,
+            source AS "source: PostSource",
+            since,
+            until,
+            next_url,
+            pages,
+            fetched_count,
+            new_count,
+            updated_count,
+            duplicate_count,
+            blacklisted_count,
+            started_at,
+            finished_at
+        "#,
+        id,
+        next_url,
+        counts.fetched,
+        counts.new,
+        counts.updated,
+        counts.duplicate,
+        counts.blacklisted
+    )
+    .fetch_one(db_pool)
+    .await?;
+    Ok(backfill)
+}
@@ -0,0 +1,170 @@
+use std::collections::HashSet;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::transform::Post;
+use config_structs::BlackList;
+
+#[derive(Serialize, Deserialize, sqlx::Type, Clone, Copy, Debug, PartialEq, Eq)]
+#[sqlx(type_name = "block_kind", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum BlockKind {
+    Author,
+    Tag,
+}
+
+#[derive(Serialize, Deserialize, sqlx::Type, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[sqlx(type_name = "blacklist_match", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMode {
+    #[default]
+    Exact,
+    CaseInsensitive,
+    Regex,
+}
+
+impl MatchMode {
+    /// Checks that `pattern` can be used with this mode.
+    pub fn validate(self, pattern: &str) -> Result<(), String> {
+        if pattern.trim().is_empty() {
+            return Err("must not be empty".to_owned());
+        }
+        match self {
+            Self::Regex => Regex::new(pattern).map(|_| ()).map_err(|e| e.to_string()),
+            Self::Exact | Self::CaseInsensitive => Ok(()),
+        }
+    }
+}
+
+/// A blacklist entry as stored in the database.
+#[derive(Debug)]
+pub struct BlacklistRule {
+    pub kind: BlockKind,
+    pub pattern: String,
+    pub match_mode: MatchMode,
+}
+
+#[derive(Default)]
+struct Patterns {
+    exact: HashSet<String>,
+    lowercase: HashSet<String>,
+    regexes: Vec<Regex>,
+}
+
+impl Patterns {
+    fn insert(&mut self, pattern: String, match_mode: MatchMode) -> Result<(), regex::Error> {
+        match match_mode {
+            MatchMode::Exact => {
+                self.exact.insert(pattern);
+            }
+            MatchMode::CaseInsensitive => {
+                self.lowercase.insert(pattern.to_lowercase());
+            }
+            MatchMode::Regex => self.regexes.push(Regex::new(&pattern)?),
+        }
+        Ok(())
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        self.exact.contains(value)
+            || (!self.lowercase.is_empty() && self.lowercase.contains(&value.to_lowercase()))
+            || self.regexes.iter().any(|r| r.is_match(value))
+    }
+}
+
+/// The blacklist compiled from the config and the database rules.
+#[derive(Default)]
+pub struct BlacklistMatcher {
+    authors: Patterns,
+    tags: Patterns,
+}
+
+impl BlacklistMatcher {
+    /// Compiles the rules on top of the configured exact matches.
+    /// Rules with an invalid regex are skipped.
+    pub fn new(config: &BlackList, rules: impl IntoIterator<Item = BlacklistRule>) -> Self {
+        let mut matcher = Self::from(config);
+        for rule in rules {
+            let patterns = match rule.kind {
+                BlockKind::Author => &mut matcher.authors,
+                BlockKind::Tag => &mut matcher.tags,
+            };
+            if let Err(e) = patterns.insert(rule.pattern.clone(), rule.match_mode) {
+                tracing::warn!("Skipping an invalid blacklist rule {:?}: {}", rule, e);
+            }
+        }
+        matcher
+    }
+
+    pub fn matches(&self, post: &Post) -> bool {
+        self.matches_fields(&post.author, post.tags.as_deref())
+    }
+
+    pub fn matches_fields(&self, author: &str, tags: Option<&[String]>) -> bool {
+        self.authors.matches(author)
+            || tags
+                .unwrap_or_default()
+                .iter()
+                .any(|tag| self.tags.matches(tag))
+    }
+}
+
+impl From<&BlackList> for BlacklistMatcher {
+    fn from(config: &BlackList) -> Self {
+        Self {
+            authors: Patterns {
+                exact: config.authors.iter().cloned().collect(),
+                ..Default::default()
+            },
+            tags: Patterns {
+                exact: config.tags.iter().cloned().collect(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(kind: BlockKind, pattern: &str, match_mode: MatchMode) -> BlacklistRule {
+        BlacklistRule {
+            kind,
+            pattern: pattern.to_owned(),
+            match_mode,
+        }
+    }
+
+    #[test]
+    fn test_match_modes() {
+        let config = BlackList {
+            authors: vec!["exact".to_owned()],
+            tags: vec![],
+        };
+        let matcher = BlacklistMatcher::new(
+            &config,
+            [
+                rule(BlockKind::Tag, "Koikatsu", MatchMode::CaseInsensitive),
+                rule(BlockKind::Tag, r"^(?i)mmd\b", MatchMode::Regex),
+                rule(BlockKind::Author, "[invalid", MatchMode::Regex),
+            ],
+        );
+        let tags = |tag: &str| Some(vec![tag.to_owned()]);
+
+        assert!(matcher.matches_fields("exact", None));
+        assert!(!matcher.matches_fields("Exact", None));
+        assert!(matcher.matches_fields("someone", tags("KOIKATSU").as_deref()));
+        assert!(!matcher.matches_fields("someone", tags("Koikatsu!").as_deref()));
+        assert!(matcher.matches_fields("someone", tags("MMD 2").as_deref()));
+        assert!(!matcher.matches_fields("someone", tags("崩坏3MMD").as_deref()));
+    }
+
+    #[test]
+    fn test_validate_regex() {
+        assert!(MatchMode::Regex.validate("[invalid").is_err());
+        assert!(MatchMode::Exact.validate(" ").is_err());
+        assert!(MatchMode::CaseInsensitive.validate("koikatsu").is_ok());
+    }
+}
@@ -0,0 +1,187 @@
+use anyhow::anyhow;
+use futures::{stream, StreamExt};
+use image::imageops::FilterType;
+use reqwest::Client;
+use sqlx::{Postgres, Transaction};
+
+use crate::Result;
+use config_structs::ScraperState;
+
+/// How many previews are downloaded at once.
+const CONCURRENCY: usize = 8;
+/// How many times a preview is downloaded before the post is left unhashed.
+const MAX_ATTEMPTS: i32 = 3;
+
+/// Hashes the previews of the posts that weren't hashed yet and marks the ones
+/// that look like an already hashed post, e.g. an artwork reposted from Pixiv to
+/// Twitter, as its duplicates. Returns the number of duplicates found.
+///
+/// The feed of the followed accounts is only compared with itself, like the API
+/// keeps it apart from the other sources. Only the posts created within
+/// `window_days` of each other are compared, which keeps the search on the
+/// `created` index.
+#[tracing::instrument(skip_all)]
+pub async fn hash_previews(state: &ScraperState) -> Result<u64> {
+    let pending = sqlx::query!(
+        r#"SELECT id, preview_link
+        FROM honkai_posts
+        WHERE preview_hashed_at IS NULL
+        ORDER BY id DESC
+        LIMIT $1"#,
+        state.dedup.batch_size
+    )
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    let mut hashes: Vec<(i32, Option<i64>)> = stream::iter(pending)
+        .map(|post| async move {
+            let hash = match preview_hash(&state.api_client, &post.preview_link).await {
+                Ok(hash) => Some(hash),
+                Err(e) => {
+                    tracing::warn!("Failed to hash the preview of post {}: {:?}", post.id, e);
+                    None
+                }
+            };
+            (post.id, hash)
+        })
+        .buffer_unordered(CONCURRENCY)
+        .collect()
+        .await;
+    // The oldest post of a batch becomes the one its copies point to.
+    hashes.sort_unstable_by_key(|(id, _)| *id);
+
+    let mut duplicates = 0;
+    for (id, hash) in hashes {
+        let Some(hash) = hash else {
+            // Retried by the next runs, until the post is left on its own.
+            sqlx::query!(
+                r#"UPDATE honkai_posts
+                SET preview_hash_attempts = preview_hash_attempts + 1,
+                    preview_hashed_at = CASE
+                        WHEN preview_hash_attempts + 1 >= $2 THEN NOW()
+                    END
+                WHERE id = $1"#,
+                id,
+                MAX_ATTEMPTS
+            )
+            .execute(&state.db_pool)
+            .await?;
+            continue;
+        };
+        let duplicate_of = sqlx::query_scalar!(
+            r#"UPDATE honkai_posts p
+            SET preview_hash = $2,
+                preview_hashed_at = NOW(),
+                preview_hash_attempts = preview_hash_attempts + 1,
+                duplicate_of = (
+                SELECT c.id
+                FROM honkai_posts c
+                WHERE c.id <> p.id
+                    AND c.duplicate_of IS NULL
+                    AND c.hidden_at IS NULL
+                    AND c.preview_hash IS NOT NULL
+                    AND (c.source = 'twitterhome') = (p.source = 'twitterhome')
+                    AND c.created BETWEEN p.created - $4::INT * INTERVAL '1 day'
+                        AND p.created + $4::INT * INTERVAL '1 day'
+                    AND BIT_COUNT((c.preview_hash # $2::BIGINT)::BIT(64)) <= $3
+                ORDER BY c.id
+                LIMIT 1
+            )
+            WHERE p.id = $1
+            RETURNING p.duplicate_of"#,
+            id,
+            hash,
+            state.dedup.max_distance,
+            state.dedup.window_days
+        )
+        .fetch_one(&state.db_pool)
+        .await?;
+        if duplicate_of.is_some() {
+            duplicates += 1;
+        }
+    }
+
+    Ok(duplicates)
+}
+
+/// Keeps the duplicates of hidden posts in the feeds: the oldest visible
+/// duplicate of each hidden post takes its place and the others point to it.
+pub(crate) async fn release_duplicates(
+    tx: &mut Transaction<'_, Postgres>,
+    hidden: &[i32],
+) -> Result<()> {
+    sqlx::query!(
+        r#"WITH orphans AS (
+            SELECT id, MIN(id) OVER (PARTITION BY duplicate_of) AS heir
+            FROM honkai_posts
+            WHERE duplicate_of = ANY($1) AND hidden_at IS NULL
+        )
+        UPDATE honkai_posts p
+        SET duplicate_of = NULLIF(o.heir, p.id)
+        FROM orphans o
+        WHERE p.id = o.id"#,
+        hidden
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+async fn preview_hash(client: &Client, url: &str) -> Result<i64> {
+    let bytes = client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    tokio::task::spawn_blocking(move || dhash(&bytes))
+        .await
+        .map_err(|e| anyhow!(e))?
+}
+
+/// A difference hash of an image: it's shrunk to 9x8 grey pixels and every bit
+/// tells whether a pixel is brighter than its right neighbour, so resized or
+/// recompressed copies get hashes only a few bits apart.
+fn dhash(bytes: &[u8]) -> Result<i64> {
+    let pixels = image::load_from_memory(bytes)
+        .map_err(|e| anyhow!(e))?
+        .grayscale()
+        .resize_exact(9, 8, FilterType::Triangle)
+        .into_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let brighter = pixels.get_pixel(x, y)[0] > pixels.get_pixel(x + 1, y)[0];
+            hash = hash << 1 | u64::from(brighter);
+        }
+    }
+    Ok(hash as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(name: &str) -> Vec<u8> {
+        std::fs::read(format!("../tests/assets/img/{name}")).unwrap()
+    }
+
+    fn distance(a: i64, b: i64) -> u32 {
+        (a ^ b).count_ones()
+    }
+
+    #[test]
+    fn test_resized_copies_have_close_hashes() {
+        let artwork = dhash(&image("artwork.png")).unwrap();
+        let copy = dhash(&image("artwork-small.png")).unwrap();
+        let other = dhash(&image("other.png")).unwrap();
+        assert!(distance(artwork, copy) <= 6);
+        assert!(distance(artwork, other) > 6);
+    }
+
+    #[test]
+    fn test_invalid_images_are_errors() {
+        assert!(dhash(b"not an image").is_err());
+    }
+}
@@ -1,60 +1,257 @@
+use std::{collections::HashSet, time::Duration};
+
+use chrono::{DateTime, Utc};
 use futures::future::join_all;
 use reqwest::Client;
+use sqlx::PgPool;
 
 use crate::{
-    save::{save_honkai_posts, save_update_time},
-    transform::{
-        DataSource, MihoyoResponse, PixivResponse, Post, TwitterHomeResponse, TwitterHonkaiResponse,
-    },
-    Result,
+    blacklist::BlacklistMatcher,
+    load::{any_post_stored, load_blacklist},
+    runs::{claim_pending_run, finish_scrape_run, start_scrape_run, CancelledRunGuard},
+    save::{save_honkai_posts, save_source_run},
+    transform::{enabled_sources, Post, PostSource, Source},
+    Error, Result,
 };
-use config_structs::{BlackList, ScraperState, SourcesUrls};
+use config_structs::{ScraperState, SourceSettings};
+
+/// The outcome of requesting a single source.
+pub struct SourceRun {
+    pub source: PostSource,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Also the posts fetched before the run failed, they are saved all the same.
+    pub posts: Vec<Post>,
+    pub error: Option<Error>,
+}
+
+/// What happened to the posts fetched from a source.
+#[derive(Default)]
+pub struct PostCounts {
+    pub fetched: i32,
+    pub new: i32,
+    /// Already stored posts whose metadata changed.
+    pub updated: i32,
+    /// Already stored posts that didn't change.
+    pub duplicate: i32,
+    pub blacklisted: i32,
+}
+
+async fn run_source(
+    client: &Client,
+    db_pool: &PgPool,
+    source: &Source,
+    settings: &SourceSettings,
+) -> SourceRun {
+    let started_at = Utc::now();
+    let timeout = Duration::from_secs(settings.timeout_secs);
+    let mut posts = Vec::new();
+    let error = tokio::time::timeout(
+        timeout,
+        crawl(client, db_pool, source, settings, &mut posts),
+    )
+    .await
+    .unwrap_or(Err(Error::Timeout))
+    .err();
+    if let Some(e) = &error {
+        tracing::error!(
+            "Failed to scrape {} after {} posts: {:?}",
+            source.id,
+            posts.len(),
+            e
+        );
+    }
+    SourceRun {
+        source: source.post_source,
+        started_at,
+        finished_at: Utc::now(),
+        posts,
+        error,
+    }
+}
+
+/// Crawls every feed of a source, one per followed board, into `posts`.
+///
+/// The posts are collected as the pages come, so they are kept when a later
+/// page fails or the run times out.
+async fn crawl(
+    client: &Client,
+    db_pool: &PgPool,
+    source: &Source,
+    settings: &SourceSettings,
+    posts: &mut Vec<Post>,
+) -> Result<()> {
+    let mut seen = HashSet::new();
+    for (i, url) in feed_urls(source, settings).into_iter().enumerate() {
+        if i > 0 {
+            tokio::time::sleep(Duration::from_millis(settings.page_delay_ms)).await;
+        }
+        crawl_feed(client, db_pool, source, settings, url, &mut seen, posts).await?;
+    }
+    Ok(())
+}
+
+/// The URLs a run of the source starts from.
+fn feed_urls(source: &Source, settings: &SourceSettings) -> Vec<String> {
+    if settings.forums.is_empty() {
+        return vec![settings.url.clone()];
+    }
+    settings
+        .forums
+        .iter()
+        .filter_map(|forum| {
+            let url = source.forum_url(&settings.url, forum);
+            if url.is_none() {
+                tracing::warn!("{} has no boards, ignoring {:?}", source.id, forum);
+            }
+            url
+        })
+        .collect()
+}
+
+/// Follows the pages of a feed until one of them contains an already
+/// stored post, there is no next page or `max_pages` are requested.
+async fn crawl_feed(
+    client: &Client,
+    db_pool: &PgPool,
+    source: &Source,
+    settings: &SourceSettings,
+    mut url: String,
+    seen: &mut HashSet<String>,
+    posts: &mut Vec<Post>,
+) -> Result<()> {
+    for page_number in 1..=settings.max_pages {
+        let page = source.request(client, &url).await?;
+        let links: Vec<String> = page.posts.iter().map(|p| p.post_link.clone()).collect();
+        let caught_up = any_post_stored(db_pool, &links).await?;
+        // New posts shift the results while paging, so a post can show up twice.
+        posts.extend(
+            page.posts
+                .into_iter()
+                .filter(|p| seen.insert(p.post_link.clone())),
+        );
+        let Some(next_url) = page.next_url else {
+            break;
+        };
+        if caught_up || page_number == settings.max_pages {
+            break;
+        }
+        tracing::debug!("Requesting page {} of {}", page_number + 1, source.id);
+        tokio::time::sleep(Duration::from_millis(settings.page_delay_ms)).await;
+        url = next_url;
+    }
+    Ok(())
+}
 
+/// Requests the sources concurrently, each one with its own timeout.
 #[tracing::instrument(skip_all)]
 pub async fn create_vec_posts(
     client: &Client,
-    blacklist: &BlackList,
-    urls: &SourcesUrls,
-) -> Vec<Post> {
-    let responses = join_all(vec![
-        PixivResponse::request_and_parse(client, &urls.pixiv),
-        TwitterHonkaiResponse::request_and_parse(client, &urls.twitter_honkai),
-        MihoyoResponse::request_and_parse(client, &urls.mihoyo),
-        TwitterHomeResponse::request_and_parse(client, &urls.twitter_home),
-    ])
-    .await;
-    responses
-        .into_iter()
-        .filter_map(Result::ok)
-        .flatten()
-        .filter(|p| !is_in_blacklist(p, blacklist))
-        .collect()
+    db_pool: &PgPool,
+    sources: &[(&Source, &SourceSettings)],
+) -> Vec<SourceRun> {
+    join_all(
+        sources
+            .iter()
+            .map(|(source, settings)| run_source(client, db_pool, source, settings)),
+    )
+    .await
 }
 
+/// Runs a scrape of every enabled source and records it in `scrape_runs`,
+/// returning the run id.
 #[tracing::instrument(skip_all)]
-pub async fn fill_db(state: &ScraperState) -> Result<String> {
-    let posts = create_vec_posts(&state.api_client, &state.blacklist, &state.sources_urls).await;
-    save_honkai_posts(&state.db_pool, posts).await?;
-    save_update_time(&state.db_pool).await?;
-    Ok(chrono::Utc::now().to_rfc3339())
+pub async fn fill_db(state: &ScraperState) -> Result<i32> {
+    let run_id = start_scrape_run(&state.db_pool, None).await?;
+    execute_run(state, run_id, None).await
+}
+
+/// Runs a scheduled scrape of a single source.
+#[tracing::instrument(skip(state))]
+pub async fn fill_db_from(state: &ScraperState, source: PostSource) -> Result<i32> {
+    let run_id = start_scrape_run(&state.db_pool, Some(&[source])).await?;
+    execute_run(state, run_id, Some(&[source])).await
 }
 
-fn is_in_blacklist(p: &Post, blacklist: &BlackList) -> bool {
-    let author_in_blacklist = blacklist.authors.contains(&p.author);
-    let tag_in_blacklist = p
-        .tags
-        .as_ref()
-        .map(|tags| tags.iter().any(|tag| blacklist.tags.contains(tag)))
-        .unwrap_or(false);
+/// Runs the scrape requested over HTTP, if there is one pending.
+#[tracing::instrument(skip_all)]
+pub async fn run_pending(state: &ScraperState) -> Result<Option<i32>> {
+    let Some((run_id, sources)) = claim_pending_run(&state.db_pool).await? else {
+        return Ok(None);
+    };
+    execute_run(state, run_id, sources.as_deref())
+        .await
+        .map(Some)
+}
+
+async fn execute_run(
+    state: &ScraperState,
+    run_id: i32,
+    sources: Option<&[PostSource]>,
+) -> Result<i32> {
+    let guard = CancelledRunGuard::new(&state.db_pool, run_id);
+    let result = scrape(state, run_id, sources).await;
+    finish_scrape_run(&state.db_pool, run_id, result.as_ref().err()).await?;
+    guard.finish();
+    result.map(|()| run_id)
+}
+
+#[tracing::instrument(skip(state))]
+async fn scrape(state: &ScraperState, run_id: i32, sources: Option<&[PostSource]>) -> Result<()> {
+    // Disabled sources are skipped even when requested explicitly.
+    let sources: Vec<_> = enabled_sources(&state.sources)
+        .into_iter()
+        .filter(|source| sources.is_none_or(|sources| sources.contains(&source.post_source)))
+        .map(|source| (source, &state.sources[source.id]))
+        .collect();
+    let blacklist = load_blacklist(&state.db_pool, &state.blacklist).await?;
+    let runs = create_vec_posts(&state.api_client, &state.db_pool, &sources).await;
+    for mut run in runs {
+        // A failed run still saves the posts it got before failing.
+        let counts = match save_posts(&state.db_pool, &blacklist, &run.posts).await {
+            Ok(counts) => counts,
+            // Recorded on the run of the source, the other sources are still saved.
+            Err(e) => {
+                tracing::error!("Failed to save the posts of {:?}: {:?}", run.source, e);
+                run.error = Some(e);
+                PostCounts::default()
+            }
+        };
+        save_source_run(&state.db_pool, run_id, &run, &counts).await?;
+    }
+    Ok(())
+}
+
+pub(crate) async fn save_posts(
+    db_pool: &PgPool,
+    blacklist: &BlacklistMatcher,
+    posts: &[Post],
+) -> Result<PostCounts> {
+    let (blacklisted, allowed): (Vec<&Post>, Vec<&Post>) =
+        posts.iter().partition(|p| is_in_blacklist(p, blacklist));
+    let saved = save_honkai_posts(db_pool, &allowed).await?;
+    tracing::debug!("Saved new posts {:?}", saved.inserted);
+    let new = saved.inserted.len() as i32;
+    let updated = saved.updated as i32;
+    Ok(PostCounts {
+        fetched: posts.len() as i32,
+        new,
+        updated,
+        duplicate: allowed.len() as i32 - new - updated,
+        blacklisted: blacklisted.len() as i32,
+    })
+}
 
-    author_in_blacklist | tag_in_blacklist
+fn is_in_blacklist(p: &Post, blacklist: &BlacklistMatcher) -> bool {
+    blacklist.matches(p)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::transform::PostSource;
+    use config_structs::BlackList;
 
     use super::*;
+    use crate::transform::MediaKind;
 
     #[test]
     fn test_check_blacklist() {
@@ -62,8 +259,10 @@ mod tests {
             author: "Icey Tashiko".to_string(),
             author_link: "https://www.pixiv.net/en/users/59611188".to_string(),
             author_profile_image: None,
-            created: "123".to_string(),
+            created: Utc::now(),
+            images: Vec::new(),
             images_number: 1,
+            media_kind: MediaKind::Photo,
             post_link: "https://www.pixiv.net/en/artworks/106611397".to_string(),
             preview_link: "https://www.morgan20.ru/en/artworks/106611397.jpg".to_string(),
             source: PostSource::Pixiv,
@@ -73,6 +272,6 @@ mod tests {
             authors: vec!["123".to_string()],
             tags: vec!["Koikatsu".to_string()],
         };
-        assert!(is_in_blacklist(&p, &blacklist));
+        assert!(is_in_blacklist(&p, &BlacklistMatcher::from(&blacklist)));
     }
 }
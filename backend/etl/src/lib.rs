@@ -1,10 +1,22 @@
+mod backfill;
+pub mod blacklist;
+mod dedup;
 mod extract;
+mod load;
+mod purge;
+pub mod runs;
 mod save;
 pub mod startup;
 mod transform;
 
+pub use backfill::{backfill, Backfill};
+pub use dedup::hash_previews;
 use errors::Error;
-pub use extract::fill_db;
-pub use transform::{Post, PostSource};
+pub use extract::{fill_db, fill_db_from, run_pending};
+pub use load::load_blacklist;
+pub use purge::{purge_blacklisted, PurgeReport};
+pub use transform::{
+    enabled_sources, MediaKind, MediaVariant, Post, PostImage, PostSource, Source, SOURCES,
+};
 
 type Result<T, E = Error> = std::result::Result<T, E>;
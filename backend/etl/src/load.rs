@@ -0,0 +1,60 @@
+use sqlx::PgPool;
+
+use crate::{
+    blacklist::{BlacklistMatcher, BlacklistRule, BlockKind, MatchMode},
+    Result,
+};
+use config_structs::BlackList;
+
+/// Compiles the configured blacklist together with the global rules and the
+/// authors and tags blocked by users, so that the scraper skips them all.
+#[tracing::instrument(skip_all)]
+pub async fn load_blacklist(db_pool: &PgPool, config: &BlackList) -> Result<BlacklistMatcher> {
+    let rules = sqlx::query_as!(
+        BlacklistRule,
+        r#"SELECT
+            kind AS "kind!: BlockKind",
+            pattern AS "pattern!",
+            match_mode AS "match_mode!: MatchMode"
+        FROM blacklist
+        UNION ALL
+        SELECT DISTINCT kind, value, 'exact'::blacklist_match
+        FROM user_blocklist"#
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    Ok(BlacklistMatcher::new(config, rules))
+}
+
+/// Compiles the configured blacklist together with the global rules only, for
+/// what applies to everyone like hiding stored posts.
+#[tracing::instrument(skip_all)]
+pub async fn load_global_blacklist(
+    db_pool: &PgPool,
+    config: &BlackList,
+) -> Result<BlacklistMatcher> {
+    let rules = sqlx::query_as!(
+        BlacklistRule,
+        r#"SELECT
+            kind AS "kind!: BlockKind",
+            pattern AS "pattern!",
+            match_mode AS "match_mode!: MatchMode"
+        FROM blacklist"#
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    Ok(BlacklistMatcher::new(config, rules))
+}
+
+/// Whether any of the posts is already in the database.
+pub async fn any_post_stored(db_pool: &PgPool, post_links: &[String]) -> Result<bool> {
+    let stored = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM honkai_posts WHERE post_link = ANY($1)) AS "stored!""#,
+        post_links
+    )
+    .fetch_one(db_pool)
+    .await?;
+    Ok(stored)
+}
@@ -1,20 +1,70 @@
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
+
 use config_builder::get_configuration;
 use etl::startup::{shutdown_signal, Application};
 use tele::setup_telemetry;
 
+#[derive(Parser)]
+#[command(about = "Scrapes the sources into the database on a schedule")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Hide the stored posts that match the current blacklist.
+    Purge {
+        /// Only report how many posts would be hidden.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Scrape the posts a source published in a past window, e.g. after downtime.
+    ///
+    /// Running it again for the same window resumes an interrupted backfill.
+    Backfill {
+        /// The id of the source in the `sources` settings.
+        #[arg(long)]
+        source: String,
+        /// The first day of the window, e.g. 2026-09-01.
+        #[arg(long)]
+        since: NaiveDate,
+        /// The last day of the window, inclusive.
+        #[arg(long)]
+        until: NaiveDate,
+    },
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
     let config = get_configuration().expect("Failed to read config");
     setup_telemetry();
 
-    Application::build(config)
-        .await
-        .run()
-        .await
-        .expect("Failed to create fill db task");
-    tracing::info!("Scraper task started");
+    let app = Application::build(config).await;
+    match cli.command {
+        Some(Command::Purge { dry_run }) => {
+            let report = app.purge(dry_run).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            tele::shutdown_tracing();
+        }
+        Some(Command::Backfill {
+            source,
+            since,
+            until,
+        }) => {
+            let backfill = app.backfill(&source, since, until).await?;
+            println!("{}", serde_json::to_string_pretty(&backfill)?);
+            tele::shutdown_tracing();
+        }
+        None => {
+            app.run().await.expect("Failed to create fill db task");
+            tracing::info!("Scraper task started");
 
-    shutdown_signal().await;
+            shutdown_signal().await;
+        }
+    }
 
     Ok(())
 }
@@ -0,0 +1,80 @@
+use sqlx::PgPool;
+
+use crate::{dedup::release_duplicates, load::load_global_blacklist, Result};
+use config_structs::BlackList;
+
+const BATCH_SIZE: i64 = 1000;
+
+#[derive(serde::Serialize, Debug, Default, PartialEq, Eq)]
+pub struct PurgeReport {
+    pub scanned: u64,
+    pub matched: u64,
+    pub hidden: u64,
+    pub dry_run: bool,
+}
+
+/// Applies the current blacklist to the stored posts, hiding the ones that match.
+/// Only the configured and the admin rules count: the blocks of a user keep
+/// new posts out, but hiding the stored ones would hide them for everyone.
+///
+/// With `dry_run` the matching posts are only counted.
+#[tracing::instrument(skip(db_pool, config))]
+pub async fn purge_blacklisted(
+    db_pool: &PgPool,
+    config: &BlackList,
+    dry_run: bool,
+) -> Result<PurgeReport> {
+    let blacklist = load_global_blacklist(db_pool, config).await?;
+    let mut report = PurgeReport {
+        dry_run,
+        ..Default::default()
+    };
+
+    let mut after_id = 0;
+    loop {
+        let batch = sqlx::query!(
+            r#"SELECT id, author, tags
+            FROM honkai_posts
+            WHERE hidden_at IS NULL AND id > $1
+            ORDER BY id
+            LIMIT $2"#,
+            after_id,
+            BATCH_SIZE
+        )
+        .fetch_all(db_pool)
+        .await?;
+        let Some(last) = batch.last() else {
+            break;
+        };
+        after_id = last.id;
+        report.scanned += batch.len() as u64;
+
+        let matched: Vec<i32> = batch
+            .into_iter()
+            .filter(|row| blacklist.matches_fields(&row.author, row.tags.as_deref()))
+            .map(|row| row.id)
+            .collect();
+        report.matched += matched.len() as u64;
+
+        if !dry_run && !matched.is_empty() {
+            let mut tx = db_pool.begin().await?;
+            let hidden = sqlx::query_scalar!(
+                "UPDATE honkai_posts SET hidden_at = NOW() WHERE id = ANY($1) AND hidden_at IS NULL RETURNING id",
+                &matched
+            )
+            .fetch_all(&mut *tx)
+            .await?;
+            release_duplicates(&mut tx, &hidden).await?;
+            tx.commit().await?;
+            report.hidden += hidden.len() as u64;
+        }
+    }
+
+    tracing::info!(
+        "Blacklist purge matched {} of {} posts, hid {}",
+        report.matched,
+        report.scanned,
+        report.hidden
+    );
+    Ok(report)
+}
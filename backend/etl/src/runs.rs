@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::{transform::PostSource, Error, Result};
+
+/// The channel the scraper listens on for requested runs.
+pub const SCRAPE_REQUESTED: &str = "scrape_requested";
+
+#[derive(Serialize, Deserialize, sqlx::Type, Clone, Copy, Debug, PartialEq, Eq)]
+#[sqlx(type_name = "scrape_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ScrapeStatus {
+    Pending,
+    Running,
+    Finished,
+    Failed,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RequestedRun {
+    pub id: i32,
+    pub status: ScrapeStatus,
+}
+
+/// Queues a run of `sources`, or of every source when `None`, and wakes the scraper up.
+///
+/// A run in progress that covers the sources is returned instead, and a pending
+/// run is extended with the sources rather than queueing another one.
+#[tracing::instrument(skip(db_pool))]
+pub async fn request_run(db_pool: &PgPool, sources: Option<&[PostSource]>) -> Result<RequestedRun> {
+    let running = sqlx::query_scalar!(
+        r#"SELECT id FROM scrape_runs
+        WHERE status = 'running'
+            AND (sources IS NULL OR (sources @> $1::post_source[]) IS TRUE)
+        ORDER BY id DESC
+        LIMIT 1"#,
+        sources as Option<&[PostSource]>
+    )
+    .fetch_optional(db_pool)
+    .await?;
+    if let Some(id) = running {
+        return Ok(RequestedRun {
+            id,
+            status: ScrapeStatus::Running,
+        });
+    }
+
+    let id = sqlx::query_scalar!(
+        r#"INSERT INTO scrape_runs (status, sources)
+        VALUES ('pending', $1)
+        ON CONFLICT (status) WHERE status = 'pending' DO UPDATE
+        SET sources = CASE
+            WHEN scrape_runs.sources IS NULL OR EXCLUDED.sources IS NULL THEN NULL
+            ELSE ARRAY(SELECT DISTINCT unnest(scrape_runs.sources || EXCLUDED.sources))
+        END
+        RETURNING id"#,
+        sources as Option<&[PostSource]>
+    )
+    .fetch_one(db_pool)
+    .await?;
+    sqlx::query!("SELECT pg_notify($1, $2)", SCRAPE_REQUESTED, id.to_string())
+        .execute(db_pool)
+        .await?;
+
+    Ok(RequestedRun {
+        id,
+        status: ScrapeStatus::Pending,
+    })
+}
+
+/// Records the start of a scheduled run of `sources`, or of every source when `None`.
+#[tracing::instrument(skip(db_pool))]
+pub async fn start_scrape_run(db_pool: &PgPool, sources: Option<&[PostSource]>) -> Result<i32> {
+    Ok(sqlx::query_scalar!(
+        "INSERT INTO scrape_runs (status, started_at, sources)
+        VALUES ('running', NOW(), $1)
+        RETURNING id",
+        sources as Option<&[PostSource]>
+    )
+    .fetch_one(db_pool)
+    .await?)
+}
+
+/// Marks the pending run as running, returning its id and sources.
+#[tracing::instrument(skip_all)]
+pub async fn claim_pending_run(db_pool: &PgPool) -> Result<Option<(i32, Option<Vec<PostSource>>)>> {
+    Ok(sqlx::query!(
+        r#"UPDATE scrape_runs
+        SET status = 'running', started_at = NOW()
+        WHERE status = 'pending'
+        RETURNING id, sources AS "sources: Vec<PostSource>""#
+    )
+    .fetch_optional(db_pool)
+    .await?
+    .map(|run| (run.id, run.sources)))
+}
+
+#[tracing::instrument(skip(db_pool))]
+pub async fn finish_scrape_run(db_pool: &PgPool, run_id: i32, error: Option<&Error>) -> Result<()> {
+    sqlx::query!(
+        r#"UPDATE scrape_runs
+        SET finished_at = NOW(),
+            error = $2::TEXT,
+            status = CASE WHEN $2 IS NULL THEN 'finished' ELSE 'failed' END::scrape_status
+        WHERE id = $1"#,
+        run_id,
+        error.map(ToString::to_string)
+    )
+    .execute(db_pool)
+    .await?;
+    Ok(())
+}
+
+/// Fails the runs left running by a scraper that was stopped midway.
+#[tracing::instrument(skip_all)]
+pub async fn fail_interrupted_runs(db_pool: &PgPool) -> Result<u64> {
+    Ok(sqlx::query!(
+        "UPDATE scrape_runs
+        SET status = 'failed', finished_at = NOW(), error = 'interrupted'
+        WHERE status = 'running'"
+    )
+    .execute(db_pool)
+    .await?
+    .rows_affected())
+}
+
+/// Fails a run whose task was dropped before it finished, e.g. a scheduled run
+/// cancelled by the timer for going over its maximum running time. Otherwise the
+/// run stays 'running' and the requested runs are merged into it until a restart.
+pub(crate) struct CancelledRunGuard {
+    db_pool: PgPool,
+    run_id: i32,
+    finished: bool,
+}
+
+impl CancelledRunGuard {
+    pub fn new(db_pool: &PgPool, run_id: i32) -> Self {
+        Self {
+            db_pool: db_pool.clone(),
+            run_id,
+            finished: false,
+        }
+    }
+
+    /// The run was recorded as finished or failed, nothing left to do on drop.
+    pub fn finish(mut self) {
+        self.finished = true;
+    }
+}
+
+impl Drop for CancelledRunGuard {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        tracing::warn!("Run {} was cancelled", self.run_id);
+        // Without a runtime the run is failed as interrupted on the next start.
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let db_pool = self.db_pool.clone();
+        let run_id = self.run_id;
+        handle.spawn(async move {
+            if let Err(e) = fail_cancelled_run(&db_pool, run_id).await {
+                tracing::error!(
+                    "Failed to mark the cancelled run {} as failed: {:?}",
+                    run_id,
+                    e
+                );
+            }
+        });
+    }
+}
+
+#[tracing::instrument(skip(db_pool))]
+async fn fail_cancelled_run(db_pool: &PgPool, run_id: i32) -> Result<()> {
+    sqlx::query!(
+        "UPDATE scrape_runs
+        SET status = 'failed', finished_at = NOW(), error = 'cancelled'
+        WHERE id = $1 AND status = 'running'",
+        run_id
+    )
+    .execute(db_pool)
+    .await?;
+    Ok(())
+}
@@ -1,13 +1,60 @@
-use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+
+use sqlx::{PgPool, Postgres, Transaction};
 
 use crate::{
-    transform::{Post, PostSource},
+    extract::{PostCounts, SourceRun},
+    transform::{MediaKind, Post, PostImage, PostSource},
     Result,
 };
 
-pub async fn save_honkai_posts(db_pool: &PgPool, posts: Vec<Post>) -> Result<()> {
-    for post in posts {
-        let Post {
+/// What [`save_honkai_posts`] stored.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SavedPosts {
+    /// The links of the posts that weren't stored yet.
+    pub inserted: Vec<String>,
+    pub updated: u64,
+}
+
+/// Inserts the posts that aren't stored yet and refreshes the metadata that
+/// changed on the stored ones, e.g. a renamed author or edited tags.
+///
+/// The whole batch is written in a single transaction.
+pub async fn save_honkai_posts(db_pool: &PgPool, posts: &[&Post]) -> Result<SavedPosts> {
+    // A row can't be upserted twice by the same statement.
+    let mut seen = HashSet::new();
+    let posts: Vec<&Post> = posts
+        .iter()
+        .copied()
+        .filter(|post| seen.insert(post.post_link.as_str()))
+        .collect();
+    if posts.is_empty() {
+        return Ok(SavedPosts::default());
+    }
+
+    let post_links: Vec<&str> = posts.iter().map(|p| p.post_link.as_str()).collect();
+    let preview_links: Vec<&str> = posts.iter().map(|p| p.preview_link.as_str()).collect();
+    let images_numbers: Vec<i32> = posts.iter().map(|p| p.images_number).collect();
+    let created: Vec<_> = posts.iter().map(|p| p.created).collect();
+    let authors: Vec<&str> = posts.iter().map(|p| p.author.as_str()).collect();
+    let author_links: Vec<&str> = posts.iter().map(|p| p.author_link.as_str()).collect();
+    let sources: Vec<PostSource> = posts.iter().map(|p| p.source).collect();
+    // Arrays of arrays have to be rectangular, so the tags go through JSON.
+    let tags: Vec<Option<serde_json::Value>> = posts
+        .iter()
+        .map(|p| p.tags.as_ref().map(|tags| serde_json::json!(tags)))
+        .collect();
+    let profile_images: Vec<Option<&str>> = posts
+        .iter()
+        .map(|p| p.author_profile_image.as_deref())
+        .collect();
+    let media_kinds: Vec<MediaKind> = posts.iter().map(|p| p.media_kind).collect();
+
+    let mut tx = db_pool.begin().await?;
+    // `xmax` is only set on the rows that were already there.
+    let rows = sqlx::query!(
+        r#"
+        INSERT INTO honkai_posts (
             post_link,
             preview_link,
             images_number,
@@ -17,45 +64,204 @@ pub async fn save_honkai_posts(db_pool: &PgPool, posts: Vec<Post>) -> Result<()>
             source,
             tags,
             author_profile_image,
-        } = post;
-        sqlx::query!(
-            r#"
-            INSERT INTO honkai_posts (
-                post_link,
-                preview_link,
-                images_number,
-                created,
-                author,
-                author_link,
-                source,
-                tags,
-                author_profile_image
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
-            ON CONFLICT (post_link) DO NOTHING
-            "#,
+            media_kind
+        )
+        SELECT
+            post_link,
+            preview_link,
+            images_number,
+            created,
+            author,
+            author_link,
+            source,
+            CASE WHEN tags IS NOT NULL THEN ARRAY(SELECT JSONB_ARRAY_ELEMENTS_TEXT(tags)) END,
+            author_profile_image,
+            media_kind
+        FROM UNNEST(
+            $1::TEXT[],
+            $2::TEXT[],
+            $3::INT[],
+            $4::TIMESTAMPTZ[],
+            $5::TEXT[],
+            $6::TEXT[],
+            $7::post_source[],
+            $8::JSONB[],
+            $9::TEXT[],
+            $10::media_kind[]
+        ) AS p (
             post_link,
             preview_link,
             images_number,
             created,
             author,
             author_link,
-            source as PostSource,
-            tags.as_deref(),
-            author_profile_image.as_deref()
+            source,
+            tags,
+            author_profile_image,
+            media_kind
         )
-        .execute(db_pool)
+        ON CONFLICT (post_link) DO UPDATE SET
+            preview_link = EXCLUDED.preview_link,
+            images_number = EXCLUDED.images_number,
+            author = EXCLUDED.author,
+            author_link = EXCLUDED.author_link,
+            tags = EXCLUDED.tags,
+            author_profile_image = EXCLUDED.author_profile_image,
+            media_kind = EXCLUDED.media_kind,
+            preview_hashed_at = CASE
+                WHEN honkai_posts.preview_link = EXCLUDED.preview_link
+                THEN honkai_posts.preview_hashed_at
+            END,
+            preview_hash_attempts = CASE
+                WHEN honkai_posts.preview_link = EXCLUDED.preview_link
+                THEN honkai_posts.preview_hash_attempts
+                ELSE 0
+            END,
+            updated_at = NOW()
+        WHERE (
+            honkai_posts.preview_link,
+            honkai_posts.images_number,
+            honkai_posts.author,
+            honkai_posts.author_link,
+            honkai_posts.tags,
+            honkai_posts.author_profile_image,
+            honkai_posts.media_kind
+        ) IS DISTINCT FROM (
+            EXCLUDED.preview_link,
+            EXCLUDED.images_number,
+            EXCLUDED.author,
+            EXCLUDED.author_link,
+            EXCLUDED.tags,
+            EXCLUDED.author_profile_image,
+            EXCLUDED.media_kind
+        )
+        RETURNING id, post_link, xmax = 0 AS "inserted!"
+        "#,
+        &post_links as &[&str],
+        &preview_links as &[&str],
+        &images_numbers,
+        &created,
+        &authors as &[&str],
+        &author_links as &[&str],
+        &sources as &[PostSource],
+        &tags as &[Option<serde_json::Value>],
+        &profile_images as &[Option<&str>],
+        &media_kinds as &[MediaKind]
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let images: HashMap<&str, &[PostImage]> = posts
+        .iter()
+        .map(|p| (p.post_link.as_str(), p.images.as_slice()))
+        .collect();
+    let saved_images: Vec<(i32, &[PostImage])> = rows
+        .iter()
+        .map(|row| (row.id, images[row.post_link.as_str()]))
+        .collect();
+    save_post_images(&mut tx, &saved_images).await?;
+    tx.commit().await?;
+
+    let mut saved = SavedPosts::default();
+    for row in rows {
+        if row.inserted {
+            saved.inserted.push(row.post_link);
+        } else {
+            saved.updated += 1;
+        }
+    }
+    Ok(saved)
+}
+
+/// Replaces the images of the saved posts.
+async fn save_post_images(
+    tx: &mut Transaction<'_, Postgres>,
+    posts: &[(i32, &[PostImage])],
+) -> Result<()> {
+    let post_ids: Vec<i32> = posts.iter().map(|(post_id, _)| *post_id).collect();
+    sqlx::query!("DELETE FROM post_images WHERE post_id = ANY($1)", &post_ids)
+        .execute(&mut **tx)
         .await?;
+
+    let images = posts.iter().flat_map(|(post_id, images)| {
+        images
+            .iter()
+            .enumerate()
+            .map(move |(position, image)| (*post_id, position as i32, image))
+    });
+    let mut image_post_ids = Vec::new();
+    let mut positions = Vec::new();
+    let mut urls = Vec::new();
+    let mut widths = Vec::new();
+    let mut heights = Vec::new();
+    let mut variants = Vec::new();
+    for (post_id, position, image) in images {
+        image_post_ids.push(post_id);
+        positions.push(position);
+        urls.push(image.url.as_str());
+        widths.push(image.width);
+        heights.push(image.height);
+        variants.push(serde_json::json!(image.variants));
     }
+    sqlx::query!(
+        r#"
+        INSERT INTO post_images (post_id, position, url, width, height, variants)
+        SELECT * FROM UNNEST($1::INT[], $2::INT[], $3::TEXT[], $4::INT[], $5::INT[], $6::JSONB[])
+        "#,
+        &image_post_ids,
+        &positions,
+        &urls as &[&str],
+        &widths as &[Option<i32>],
+        &heights as &[Option<i32>],
+        &variants
+    )
+    .execute(&mut **tx)
+    .await?;
     Ok(())
 }
 
-#[tracing::instrument(skip_all)]
-pub async fn save_update_time(db_pool: &PgPool) -> Result<()> {
+#[tracing::instrument(skip(db_pool, run, counts), fields(source = ?run.source))]
+pub async fn save_source_run(
+    db_pool: &PgPool,
+    run_id: i32,
+    run: &SourceRun,
+    counts: &PostCounts,
+) -> Result<()> {
+    let error_kind = run.error.as_ref().map(|e| e.kind());
+    let error = run.error.as_ref().map(ToString::to_string);
+    let duration_ms = (run.finished_at - run.started_at).num_milliseconds() as i32;
     sqlx::query!(
-        "UPDATE last_update_time
-        SET last_update_time = NOW()
-        WHERE id = 0;"
+        r#"
+        INSERT INTO source_runs (
+            run_id,
+            source,
+            started_at,
+            finished_at,
+            success,
+            error_kind,
+            error,
+            duration_ms,
+            fetched_count,
+            new_count,
+            updated_count,
+            duplicate_count,
+            blacklisted_count
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+        "#,
+        run_id,
+        run.source as PostSource,
+        run.started_at,
+        run.finished_at,
+        run.error.is_none(),
+        error_kind,
+        error,
+        duration_ms,
+        counts.fetched,
+        counts.new,
+        counts.updated,
+        counts.duplicate,
+        counts.blacklisted
     )
     .execute(db_pool)
     .await?;
@@ -1,16 +1,37 @@
-use std::{fs, sync::Arc, time::Duration};
+use std::{collections::HashMap, fs, sync::Arc, time::Duration};
 
 use axum::extract::FromRef;
+use backon::{ExponentialBuilder, Retryable};
+use chrono::NaiveDate;
 use delay_timer::prelude::{DelayTimerBuilder, TaskBuilder, TaskInstancesChain};
 
 use reqwest::{header, Client};
 use secrecy::ExposeSecret;
-use sqlx::{postgres::PgPoolOptions, PgPool};
-use tokio::signal;
+use sqlx::{
+    postgres::{PgListener, PgPoolOptions},
+    PgPool,
+};
+use tokio::{
+    signal,
+    sync::{Mutex, OwnedMutexGuard},
+};
 
-use crate::{extract::fill_db, Error};
+use crate::{
+    backfill::{backfill, Backfill},
+    dedup::hash_previews,
+    extract::{fill_db, fill_db_from, run_pending},
+    purge::{purge_blacklisted, PurgeReport},
+    runs::{fail_interrupted_runs, SCRAPE_REQUESTED},
+    transform::{enabled_sources, SOURCES},
+    Error,
+};
 use config_structs::{DatabaseSettings, ScraperState, Settings};
 
+/// Time left to a scheduled run to save the posts once the requests time out.
+const SAVE_TIME_SECS: u64 = 60;
+/// The longest wait between two attempts to connect the run request listener.
+const LISTENER_MAX_DELAY: Duration = Duration::from_secs(60);
+
 #[derive(Clone)]
 pub struct StateWrapper(Arc<ScraperState>);
 
@@ -22,6 +43,46 @@ impl FromRef<StateWrapper> for ScraperState {
 
 pub struct Application {
     state: Arc<ScraperState>,
+    scrape_locks: ScrapeLocks,
+}
+
+/// A lock per source, so that a source is never scraped by two runs at once
+/// while the schedules of different sources don't wait for each other.
+///
+/// The scheduled runs have a maximum running time, so they never wait: a run
+/// is skipped when its sources are already being scraped.
+#[derive(Clone)]
+struct ScrapeLocks(Arc<HashMap<&'static str, Arc<Mutex<()>>>>);
+
+impl ScrapeLocks {
+    fn new() -> Self {
+        Self(Arc::new(
+            SOURCES
+                .iter()
+                .map(|source| (source.id, Arc::default()))
+                .collect(),
+        ))
+    }
+
+    fn try_lock(&self, id: &str) -> Option<OwnedMutexGuard<()>> {
+        self.0[id].clone().try_lock_owned().ok()
+    }
+
+    fn try_lock_all(&self) -> Option<Vec<OwnedMutexGuard<()>>> {
+        SOURCES
+            .iter()
+            .map(|source| self.try_lock(source.id))
+            .collect()
+    }
+
+    /// Waits for every source, always in the same order.
+    async fn lock_all(&self) -> Vec<OwnedMutexGuard<()>> {
+        let mut guards = Vec::with_capacity(SOURCES.len());
+        for source in SOURCES {
+            guards.push(self.0[source.id].clone().lock_owned().await);
+        }
+        guards
+    }
 }
 
 impl Application {
@@ -66,38 +127,123 @@ impl Application {
             .build()?)
     }
 
-    pub async fn run(self) -> Result<(TaskInstancesChain, TaskInstancesChain), Error> {
-        let body = move || {
-            let state = self.state.clone();
+    pub async fn run(self) -> Result<Vec<TaskInstancesChain>, Error> {
+        let interrupted = fail_interrupted_runs(&self.state.db_pool).await?;
+        if interrupted > 0 {
+            tracing::warn!("Marked {} interrupted runs as failed", interrupted);
+        }
+        tokio::spawn(listen_for_requests(
+            self.state.clone(),
+            self.scrape_locks.clone(),
+        ));
+
+        for id in self.state.sources.keys() {
+            if !SOURCES.iter().any(|source| source.id == id) {
+                tracing::warn!("Ignoring the settings of an unknown source {:?}", id);
+            }
+        }
+
+        let timer = DelayTimerBuilder::default().build();
+        let mut task_builder = TaskBuilder::default();
+        let mut chains = Vec::new();
+
+        let state = self.state.clone();
+        let scrape_locks = self.scrape_locks.clone();
+        let initial_run = move || {
+            let state = state.clone();
+            let scrape_locks = scrape_locks.clone();
             async move {
+                let Some(_guards) = scrape_locks.try_lock_all() else {
+                    tracing::info!("Skipping the initial run, a requested run is in progress");
+                    return Ok(());
+                };
                 match fill_db(&state).await {
                     Err(e) => tracing::error!("Failed to fill db. Err: {:?}", e),
-                    Ok(_) => tracing::info!("DB filled"),
+                    Ok(run_id) => tracing::info!("DB filled, run {}", run_id),
                 };
                 Ok::<(), Error>(())
             }
         };
-
-        let mut task_builder = TaskBuilder::default();
-
+        let longest_timeout = self
+            .state
+            .sources
+            .values()
+            .map(|s| s.timeout_secs)
+            .max()
+            .unwrap_or_default();
         let initial_task = task_builder
             .set_task_id(0)
             .set_frequency_once_by_seconds(1)
             .set_maximum_parallel_runnable_num(1)
-            .set_maximum_running_time(30)
-            .spawn_async_routine(body.clone());
+            .set_maximum_running_time(longest_timeout + SAVE_TIME_SECS)
+            .spawn_async_routine(initial_run)?;
+        chains.push(timer.insert_task(initial_task)?);
+
+        for (task_id, source) in (1..).zip(enabled_sources(&self.state.sources)) {
+            let settings = &self.state.sources[source.id];
+            let id = source.id;
+            let source = source.post_source;
+            let state = self.state.clone();
+            let scrape_locks = self.scrape_locks.clone();
+            let scheduled_run = move || {
+                let state = state.clone();
+                let scrape_locks = scrape_locks.clone();
+                async move {
+                    let Some(_guard) = scrape_locks.try_lock(id) else {
+                        tracing::info!("Skipping {:?}, it is already being scraped", source);
+                        return Ok(());
+                    };
+                    match fill_db_from(&state, source).await {
+                        Err(e) => {
+                            tracing::error!("Failed to fill db from {:?}. Err: {:?}", source, e)
+                        }
+                        Ok(run_id) => tracing::info!("DB filled from {:?}, run {}", source, run_id),
+                    };
+                    Ok::<(), Error>(())
+                }
+            };
+            let task = task_builder
+                .set_task_id(task_id)
+                .set_frequency_repeated_by_cron_str(&settings.cron)
+                .set_maximum_parallel_runnable_num(1)
+                .set_maximum_running_time(settings.timeout_secs + SAVE_TIME_SECS)
+                .spawn_async_routine(scheduled_run)?;
+            chains.push(timer.insert_task(task)?);
+            tracing::info!("Scheduled {:?} with {:?}", source, settings.cron);
+        }
 
-        let continious_task = task_builder
-            .set_task_id(1)
-            .set_frequency_repeated_by_cron_str("0 */20 * * * *")
+        let state = self.state.clone();
+        let dedup_run = move || {
+            let state = state.clone();
+            async move {
+                match hash_previews(&state).await {
+                    Err(e) => tracing::error!("Failed to hash the previews. Err: {:?}", e),
+                    Ok(duplicates) => tracing::info!("Previews hashed, {} duplicates", duplicates),
+                };
+                Ok::<(), Error>(())
+            }
+        };
+        let dedup_task = task_builder
+            .set_task_id(SOURCES.len() as u64 + 1)
+            .set_frequency_repeated_by_cron_str(&self.state.dedup.cron)
             .set_maximum_parallel_runnable_num(1)
-            .set_maximum_running_time(30)
-            .spawn_async_routine(body);
+            .spawn_async_routine(dedup_run)?;
+        chains.push(timer.insert_task(dedup_task)?);
 
-        let timer = DelayTimerBuilder::default().build();
-        let initial_task = timer.insert_task(initial_task?)?;
-        let continious_task_chain = timer.insert_task(continious_task?)?;
-        Ok((initial_task, continious_task_chain))
+        Ok(chains)
+    }
+
+    pub async fn purge(&self, dry_run: bool) -> Result<PurgeReport, Error> {
+        purge_blacklisted(&self.state.db_pool, &self.state.blacklist, dry_run).await
+    }
+
+    pub async fn backfill(
+        &self,
+        source: &str,
+        since: NaiveDate,
+        until: NaiveDate,
+    ) -> Result<Backfill, Error> {
+        backfill(&self.state, source, since, until).await
     }
 
     fn get_connection_pool(config: &DatabaseSettings) -> PgPool {
@@ -113,13 +259,68 @@ impl Application {
             db_pool,
             api_client: Self::create_api_client(&config).expect("Failed to create the api client"),
             blacklist: config.app.blacklist,
-            sources_urls: config.app.sources_urls,
+            sources: config.app.sources,
+            dedup: config.app.dedup,
         });
 
-        Self { state }
+        Self {
+            state,
+            scrape_locks: ScrapeLocks::new(),
+        }
     }
 }
 
+/// Runs the scrapes requested over HTTP, see [`crate::runs::request_run`].
+///
+/// The listener reconnects whenever its connection is lost, so a database
+/// restart doesn't leave the requests unanswered until the scraper restarts.
+async fn listen_for_requests(state: Arc<ScraperState>, scrape_locks: ScrapeLocks) {
+    loop {
+        let mut listener = connect_listener(&state.db_pool).await;
+        loop {
+            // Also picks up the runs requested while the listener was down.
+            loop {
+                let _guards = scrape_locks.lock_all().await;
+                match run_pending(&state).await {
+                    Ok(Some(run_id)) => tracing::info!("DB filled, requested run {}", run_id),
+                    Ok(None) => break,
+                    Err(e) => {
+                        tracing::error!("Failed to run a requested scrape. Err: {:?}", e);
+                        break;
+                    }
+                }
+            }
+            if let Err(e) = listener.recv().await {
+                tracing::error!("Failed to receive a run request, reconnecting: {:?}", e);
+                break;
+            }
+        }
+    }
+}
+
+/// Connects a listener to [`SCRAPE_REQUESTED`], retrying until it succeeds.
+async fn connect_listener(db_pool: &PgPool) -> PgListener {
+    (|| async {
+        let mut listener = PgListener::connect_with(db_pool).await?;
+        listener.listen(SCRAPE_REQUESTED).await?;
+        Ok::<_, sqlx::Error>(listener)
+    })
+    .retry(
+        ExponentialBuilder::default()
+            .with_max_delay(LISTENER_MAX_DELAY)
+            .without_max_times(),
+    )
+    .notify(|err: &sqlx::Error, dur: Duration| {
+        tracing::error!(
+            "Failed to listen for run requests, retrying after {:?}: {:?}",
+            dur,
+            err
+        );
+    })
+    .await
+    .expect("retries forever")
+}
+
 fn read_headers() -> Result<header::HeaderMap, Error> {
     let contents = fs::read_to_string("headers.txt").map_err(|e| anyhow::anyhow!(e))?;
 
@@ -162,3 +363,22 @@ pub async fn shutdown_signal() {
 
     tele::shutdown_tracing();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sources_are_locked_separately() {
+        let locks = ScrapeLocks::new();
+        let pixiv = locks.try_lock("pixiv").unwrap();
+        assert!(locks.try_lock("pixiv").is_none());
+        assert!(locks.try_lock("mihoyo").is_some());
+        assert!(locks.try_lock_all().is_none());
+
+        drop(pixiv);
+        let all = locks.lock_all().await;
+        assert_eq!(all.len(), SOURCES.len());
+        assert!(locks.try_lock("mihoyo").is_none());
+    }
+}
@@ -0,0 +1,160 @@
+use chrono::{TimeZone, Utc};
+use serde::Deserialize;
+
+use crate::transform::{MediaKind, Post, PostImage, PostSource};
+
+use super::{with_query, DataSource};
+
+/// A circle feed of BCY (半次元). The site itself is closed, the source is kept
+/// for its archives and the communities that serve the same API.
+#[derive(Deserialize)]
+pub struct BcyResponse {
+    data: BcyData,
+}
+
+#[derive(Deserialize)]
+struct BcyData {
+    items: Vec<BcyItem>,
+}
+
+#[derive(Deserialize)]
+struct BcyItem {
+    /// Where the next page starts when this item is the last one.
+    since: String,
+    item_detail: ItemDetail,
+}
+
+#[derive(Deserialize)]
+struct ItemDetail {
+    item_id: String,
+    uid: i64,
+    uname: String,
+    avatar: Option<String>,
+    ctime: i64,
+    #[serde(default)]
+    post_tags: Vec<PostTag>,
+    /// Empty on videos and text posts.
+    #[serde(default)]
+    image_list: Vec<Image>,
+}
+
+#[derive(Deserialize)]
+struct PostTag {
+    tag_name: String,
+}
+
+#[derive(Deserialize)]
+struct Image {
+    /// A resized copy.
+    path: String,
+    origin: String,
+    w: Option<i32>,
+    h: Option<i32>,
+}
+
+impl From<ItemDetail> for Post {
+    fn from(value: ItemDetail) -> Self {
+        let created = Utc.timestamp_opt(value.ctime, 0).unwrap();
+        let preview_link = value.image_list[0].path.clone();
+        let images: Vec<PostImage> = value
+            .image_list
+            .into_iter()
+            .map(|image| PostImage {
+                url: image.origin,
+                width: image.w,
+                height: image.h,
+                variants: Vec::new(),
+            })
+            .collect();
+        Self {
+            preview_link,
+            post_link: format!("https://bcy.net/item/detail/{}", value.item_id),
+            author_link: format!("https://bcy.net/u/{}", value.uid),
+            author: value.uname,
+            created,
+            source: PostSource::Bcy,
+            images_number: images.len() as i32,
+            images,
+            media_kind: MediaKind::Photo,
+            tags: Some(
+                value
+                    .post_tags
+                    .into_iter()
+                    .map(|tag| tag.tag_name)
+                    .collect(),
+            ),
+            author_profile_image: value.avatar.filter(|avatar| !avatar.is_empty()),
+        }
+    }
+}
+
+impl From<BcyResponse> for Vec<Post> {
+    fn from(value: BcyResponse) -> Self {
+        value
+            .data
+            .items
+            .into_iter()
+            .map(|item| item.item_detail)
+            .filter(|item| !item.image_list.is_empty())
+            .map(Post::from)
+            .collect()
+    }
+}
+
+impl DataSource for BcyResponse {
+    const ID: &'static str = "bcy";
+    const POST_SOURCE: PostSource = PostSource::Bcy;
+
+    /// The feed continues from the `since` of its last item.
+    fn next_page_url(&self, url: &str) -> Option<String> {
+        let since = &self.data.items.last()?.since;
+        let url = reqwest::Url::parse(url).ok()?;
+        Some(with_query(url, &[("since", since.clone())]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    const BCY_JSON_PATH: &str = "../tests/assets/json/bcy.json";
+
+    fn response() -> BcyResponse {
+        let json = fs::read_to_string(BCY_JSON_PATH).expect("Unable to read the file");
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn test_from_bcy_response_to_vec_posts() {
+        let posts = Vec::<Post>::from(response());
+        // The video has no images.
+        assert_eq!(posts.len(), 3);
+        let post = &posts[0];
+        assert_eq!(
+            post.post_link,
+            "https://bcy.net/item/detail/7156219443125329703"
+        );
+        assert_eq!(post.author, "月下舰长");
+        assert_eq!(post.author_link, "https://bcy.net/u/3887301528");
+        assert!(post.author_profile_image.is_some());
+        assert_eq!(post.created.to_rfc3339(), "2022-10-18T13:00:00+00:00");
+        assert_eq!(post.images_number, 2);
+        assert_eq!(post.images[1].height, Some(1697));
+        assert_eq!(post.tags.as_deref().unwrap(), ["崩坏3", "琪亚娜", "绘画"]);
+        assert!(posts[2].author_profile_image.is_none());
+    }
+
+    #[test]
+    fn test_next_page_url() {
+        let url = "https://bcy.net/apiv3/common/circleFeed?circle_id=109315&since=0";
+        assert_eq!(
+            response().next_page_url(url).unwrap(),
+            "https://bcy.net/apiv3/common/circleFeed?circle_id=109315&since=rec%3A7156180937014362400"
+        );
+
+        let empty: BcyResponse = serde_json::from_str(r#"{"data": {"items": []}}"#).unwrap();
+        assert!(empty.next_page_url(url).is_none());
+    }
+}
@@ -0,0 +1,83 @@
+use serde::Deserialize;
+
+use crate::transform::{Post, PostSource};
+
+use super::{
+    mihoyo_bbs::{forum_url, MihoyoResponse, Site},
+    DataSource,
+};
+use config_structs::ForumSettings;
+
+const HOYOLAB: Site = Site {
+    source: PostSource::Hoyolab,
+    url: "https://www.hoyolab.com",
+    game_paths: false,
+};
+
+/// A forum of HoYoLAB, the international counterpart of miyoushe.
+#[derive(Deserialize)]
+#[serde(transparent)]
+pub struct HoyolabResponse(MihoyoResponse);
+
+impl From<HoyolabResponse> for Vec<Post> {
+    fn from(value: HoyolabResponse) -> Self {
+        value.0.into_posts(&HOYOLAB)
+    }
+}
+
+impl DataSource for HoyolabResponse {
+    const ID: &'static str = "hoyolab";
+    const POST_SOURCE: PostSource = PostSource::Hoyolab;
+
+    fn next_page_url(&self, url: &str) -> Option<String> {
+        self.0.next_forum_page_url(url)
+    }
+
+    fn forum_url(url: &str, forum: &ForumSettings) -> Option<String> {
+        forum_url(url, forum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    const HOYOLAB_JSON_PATH: &str = "../tests/assets/json/hoyolab.json";
+
+    fn response() -> HoyolabResponse {
+        let json = fs::read_to_string(HOYOLAB_JSON_PATH).expect("Unable to read the file");
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn test_from_hoyolab_response_to_vec_posts() {
+        let posts = Vec::<Post>::from(response());
+        // The discussion has no cover.
+        assert_eq!(posts.len(), 2);
+        let post = &posts[0];
+        assert_eq!(post.post_link, "https://www.hoyolab.com/article/41872210");
+        assert_eq!(
+            post.author_link,
+            "https://www.hoyolab.com/accountCenter/postList?id=280561722"
+        );
+        assert_eq!(post.author, "Senadina Fan");
+        assert!(post.author_profile_image.is_some());
+        assert_eq!(post.source, PostSource::Hoyolab);
+        assert_eq!(post.created.to_rfc3339(), "2025-10-18T12:00:00+00:00");
+        assert_eq!(post.images_number, 2);
+        assert_eq!(post.images[0].width, Some(2894));
+        assert_eq!(post.tags.as_deref().unwrap(), ["Fan Art", "Senadina"]);
+    }
+
+    #[test]
+    fn test_next_page_url() {
+        let url =
+            "https://bbs-api-os.hoyolab.com/community/post/wapi/getForumPostList?forum_id=4&gids=1";
+        assert_eq!(
+            response().next_page_url(url).unwrap(),
+            "https://bbs-api-os.hoyolab.com/community/post/wapi/getForumPostList?forum_id=4&gids=1&last_id=41871502"
+        );
+    }
+}
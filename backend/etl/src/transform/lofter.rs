@@ -0,0 +1,190 @@
+use chrono::{TimeZone, Utc};
+use serde::Deserialize;
+
+use crate::transform::{MediaKind, Post, PostImage, PostSource};
+
+use super::{with_query, DataSource};
+
+#[derive(Deserialize)]
+pub struct LofterResponse {
+    data: LofterData,
+}
+
+#[derive(Deserialize)]
+struct LofterData {
+    list: Vec<LofterEntry>,
+    /// The `offset` of the next page.
+    offset: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LofterEntry {
+    post_data: PostData,
+    blog_info: BlogInfo,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PostData {
+    post_view: PostView,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PostView {
+    permalink: String,
+    /// Milliseconds since the epoch.
+    publish_time: i64,
+    #[serde(default)]
+    tag_list: Vec<String>,
+    /// Missing on text posts.
+    photo_post_view: Option<PhotoPostView>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PhotoPostView {
+    photo_links: Vec<PhotoLink>,
+}
+
+#[derive(Deserialize)]
+struct PhotoLink {
+    /// The original image, the misspelling is Lofter's.
+    orign: String,
+    ow: Option<i32>,
+    oh: Option<i32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlogInfo {
+    blog_name: String,
+    blog_nick_name: String,
+    avatar_img: Option<String>,
+}
+
+impl LofterEntry {
+    fn into_post(self, photos: Vec<PhotoLink>) -> Post {
+        let post = self.post_data.post_view;
+        let blog = self.blog_info;
+        let created = Utc.timestamp_millis_opt(post.publish_time).unwrap();
+        let images: Vec<PostImage> = photos
+            .into_iter()
+            .map(|photo| PostImage {
+                url: photo.orign,
+                width: photo.ow,
+                height: photo.oh,
+                variants: Vec::new(),
+            })
+            .collect();
+        Post {
+            preview_link: format!(
+                "{}?imageView&thumbnail=600x0&quality=80&type=jpg",
+                images[0].url
+            ),
+            post_link: format!(
+                "https://{}.lofter.com/post/{}",
+                blog.blog_name, post.permalink
+            ),
+            author_link: format!("https://{}.lofter.com/", blog.blog_name),
+            author: blog.blog_nick_name,
+            created,
+            source: PostSource::Lofter,
+            images_number: images.len() as i32,
+            images,
+            media_kind: MediaKind::Photo,
+            tags: Some(post.tag_list),
+            author_profile_image: blog.avatar_img,
+        }
+    }
+}
+
+impl From<LofterResponse> for Vec<Post> {
+    fn from(value: LofterResponse) -> Self {
+        value
+            .data
+            .list
+            .into_iter()
+            .filter_map(|mut entry| {
+                let photos = entry
+                    .post_data
+                    .post_view
+                    .photo_post_view
+                    .take()
+                    .map(|view| view.photo_links)
+                    .filter(|photos| !photos.is_empty())?;
+                Some(entry.into_post(photos))
+            })
+            .collect()
+    }
+}
+
+impl DataSource for LofterResponse {
+    const ID: &'static str = "lofter";
+    const POST_SOURCE: PostSource = PostSource::Lofter;
+
+    /// The tag search is paged by the `offset` the response ends at.
+    fn next_page_url(&self, url: &str) -> Option<String> {
+        if self.data.list.is_empty() {
+            return None;
+        }
+        let offset = self.data.offset?;
+        let url = reqwest::Url::parse(url).ok()?;
+        Some(with_query(url, &[("offset", offset.to_string())]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    const LOFTER_JSON_PATH: &str = "../tests/assets/json/lofter.json";
+
+    fn posts() -> Vec<Post> {
+        let json = fs::read_to_string(LOFTER_JSON_PATH).expect("Unable to read the file");
+        serde_json::from_str::<LofterResponse>(&json)
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn test_from_lofter_response_to_vec_posts() {
+        let posts = posts();
+        // Text posts have nothing to show.
+        assert_eq!(posts.len(), 3);
+        let post = &posts[0];
+        assert_eq!(
+            post.post_link,
+            "https://hoshizora-bh3.lofter.com/post/1e093e87_1d1300476"
+        );
+        assert_eq!(post.author, "星空下的舰长");
+        assert_eq!(post.author_link, "https://hoshizora-bh3.lofter.com/");
+        assert!(post.author_profile_image.is_some());
+        assert_eq!(post.created.to_rfc3339(), "2025-10-18T07:42:00+00:00");
+        assert_eq!(post.images_number, 3);
+        assert_eq!(post.images[0].width, Some(2480));
+        assert!(post.preview_link.starts_with(&post.images[0].url));
+        assert_eq!(
+            post.tags.as_deref().unwrap(),
+            ["崩坏3", "琪亚娜·卡斯兰娜", "同人"]
+        );
+    }
+
+    #[test]
+    fn test_next_page_url() {
+        let json = fs::read_to_string(LOFTER_JSON_PATH).expect("Unable to read the file");
+        let response: LofterResponse = serde_json::from_str(&json).unwrap();
+        let url = "https://api.lofter.com/newapi/tagPosts.json?tag=%E5%B4%A9%E5%9D%8F3&offset=0";
+        assert_eq!(
+            response.next_page_url(url).unwrap(),
+            "https://api.lofter.com/newapi/tagPosts.json?tag=%E5%B4%A9%E5%9D%8F3&offset=20"
+        );
+
+        let empty: LofterResponse =
+            serde_json::from_str(r#"{"data": {"list": [], "offset": 40}}"#).unwrap();
+        assert!(empty.next_page_url(url).is_none());
+    }
+}
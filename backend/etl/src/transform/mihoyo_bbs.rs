@@ -1,9 +1,61 @@
 use chrono::{TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::transform::{Post, PostSource};
+use crate::transform::{MediaKind, Post, PostImage, PostSource};
 
-use super::DataSource;
+use super::{with_query, DataSource};
+use config_structs::ForumSettings;
+
+/// A community built on the Mihoyo forum API, which only differ by their site.
+pub(super) struct Site {
+    pub source: PostSource,
+    pub url: &'static str,
+    /// Whether the pages are under the path of their game.
+    pub game_paths: bool,
+}
+
+impl Site {
+    /// The root of the pages of a post, `None` when its game is unknown.
+    fn path(&self, game_id: u32) -> Option<String> {
+        if self.game_paths {
+            Some(format!("{}/{}", self.url, game_path(game_id)?))
+        } else {
+            Some(self.url.to_owned())
+        }
+    }
+}
+
+/// The path of a game on miyoushe, by its `gids`.
+fn game_path(game_id: u32) -> Option<&'static str> {
+    match game_id {
+        1 => Some("bh3"),
+        2 => Some("ys"),
+        3 => Some("bh2"),
+        4 => Some("wd"),
+        5 => Some("dby"),
+        6 => Some("sr"),
+        8 => Some("zzz"),
+        _ => None,
+    }
+}
+
+const MIYOUSHE: Site = Site {
+    source: PostSource::Mihoyo,
+    url: "https://www.miyoushe.com",
+    game_paths: true,
+};
+
+/// The forum list `url` pointed at another board.
+pub(super) fn forum_url(url: &str, forum: &ForumSettings) -> Option<String> {
+    let url = reqwest::Url::parse(url).ok()?;
+    Some(with_query(
+        url,
+        &[
+            ("forum_id", forum.forum_id.to_string()),
+            ("gids", forum.gids.to_string()),
+        ],
+    ))
+}
 
 #[derive(Serialize, Deserialize)]
 struct User {
@@ -14,21 +66,44 @@ struct User {
 
 #[derive(Serialize, Deserialize)]
 struct Details {
+    #[serde(default)]
+    game_id: u32,
     post_id: String,
     cover: Option<String>,
     created_at: i64,
     images: Vec<String>,
 }
 
+#[derive(Serialize, Deserialize)]
+struct Image {
+    url: String,
+    width: i32,
+    height: i32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Topic {
+    name: String,
+}
+
 #[derive(Serialize, Deserialize)]
 struct MihoyoPost {
     post: Details,
     user: User,
+    /// The images of the post with their sizes.
+    #[serde(default)]
+    image_list: Vec<Image>,
+    #[serde(default)]
+    topics: Vec<Topic>,
 }
 
 #[derive(Serialize, Deserialize)]
 struct MihiyoData {
     list: Vec<MihoyoPost>,
+    /// The id to continue the list from.
+    last_id: Option<String>,
+    #[serde(default)]
+    is_last: bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -37,42 +112,101 @@ pub struct MihoyoResponse {
     data: MihiyoData,
 }
 
-impl From<MihoyoPost> for Post {
-    fn from(value: MihoyoPost) -> Self {
-        let created = Utc
-            .timestamp_opt(value.post.created_at, 0)
-            .unwrap()
-            .to_rfc3339();
-        Self {
-            post_link: format!("https://www.miyoushe.com/bh3/article/{}", value.post.post_id),
-            preview_link: format!("{}?x-oss-process=image/resize,s_600/quality,q_80/auto-orient,0/interlace,1/format,jpg", value.post.cover.unwrap()),
-            author_link: format!(
-                "https://www.miyoushe.com/bh3/accountCenter/postList?id={}",
-                value.user.uid
-            ),
-            author: value.user.nickname,
-            created,
-            source: PostSource::Mihoyo,
-            images_number: value.post.images.len() as i32,
-            tags: None,
-            author_profile_image: Some(value.user.avatar_url),
+impl MihoyoResponse {
+    pub(super) fn into_posts(self, site: &Site) -> Vec<Post> {
+        self.data
+            .list
+            .into_iter()
+            .filter(|p| {
+                p.post
+                    .cover
+                    .as_deref()
+                    .is_some_and(|cover| !cover.is_empty())
+            })
+            .filter_map(|p| p.into_post(site))
+            .collect()
+    }
+
+    /// The forum lists are paged by the `last_id` of the previous page.
+    pub(super) fn next_forum_page_url(&self, url: &str) -> Option<String> {
+        if self.data.is_last || self.data.list.is_empty() {
+            return None;
         }
+        let last_id = self.data.last_id.clone().filter(|id| !id.is_empty())?;
+        let url = reqwest::Url::parse(url).ok()?;
+        Some(with_query(url, &[("last_id", last_id)]))
+    }
+}
+
+impl MihoyoPost {
+    /// Skips the posts of an unknown game, their links would lead nowhere.
+    fn into_post(self, site: &Site) -> Option<Post> {
+        let Some(path) = site.path(self.post.game_id) else {
+            tracing::warn!(
+                "Skipping the post {} of an unknown game {}",
+                self.post.post_id,
+                self.post.game_id
+            );
+            return None;
+        };
+        let created = Utc.timestamp_opt(self.post.created_at, 0).unwrap();
+        let images = if self.image_list.is_empty() {
+            self.post
+                .images
+                .iter()
+                .map(|url| PostImage {
+                    url: url.clone(),
+                    width: None,
+                    height: None,
+                    variants: Vec::new(),
+                })
+                .collect()
+        } else {
+            self.image_list
+                .into_iter()
+                .map(|image| PostImage {
+                    url: image.url,
+                    width: Some(image.width),
+                    height: Some(image.height),
+                    variants: Vec::new(),
+                })
+                .collect()
+        };
+        Some(Post {
+            post_link: format!("{}/article/{}", path, self.post.post_id),
+            preview_link: format!("{}?x-oss-process=image/resize,s_600/quality,q_80/auto-orient,0/interlace,1/format,jpg", self.post.cover.unwrap()),
+            author_link: format!("{}/accountCenter/postList?id={}", path, self.user.uid),
+            author: self.user.nickname,
+            created,
+            source: site.source,
+            images,
+            images_number: self.post.images.len() as i32,
+            media_kind: MediaKind::Photo,
+            // The topics are the tags of the forums.
+            tags: Some(self.topics.into_iter().map(|topic| topic.name).collect()),
+            author_profile_image: Some(self.user.avatar_url),
+        })
     }
 }
 
 impl From<MihoyoResponse> for Vec<Post> {
     fn from(value: MihoyoResponse) -> Self {
-        value
-            .data
-            .list
-            .into_iter()
-            .filter(|p| p.post.cover.is_some())
-            .map(Post::from)
-            .collect()
+        value.into_posts(&MIYOUSHE)
     }
 }
 
-impl DataSource for MihoyoResponse {}
+impl DataSource for MihoyoResponse {
+    const ID: &'static str = "mihoyo";
+    const POST_SOURCE: PostSource = PostSource::Mihoyo;
+
+    fn next_page_url(&self, url: &str) -> Option<String> {
+        self.next_forum_page_url(url)
+    }
+
+    fn forum_url(url: &str, forum: &ForumSettings) -> Option<String> {
+        forum_url(url, forum)
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -87,4 +221,79 @@ mod tests {
         let sample_json = fs::read_to_string(SAMPLE_JSON_PATH).expect("Unable to read the file");
         serde_json::from_str::<MihoyoResponse>(&sample_json).unwrap();
     }
+
+    #[test]
+    fn test_images_have_sizes() {
+        let sample_json = fs::read_to_string(SAMPLE_JSON_PATH).expect("Unable to read the file");
+        let response = serde_json::from_str::<MihoyoResponse>(&sample_json).unwrap();
+        let posts = Vec::<Post>::from(response);
+        assert!(posts.iter().all(|p| !p.images.is_empty()));
+        assert_eq!(posts[0].images[0].width, Some(3000));
+        assert_eq!(posts[0].images[0].height, Some(5918));
+    }
+
+    #[test]
+    fn test_topics_are_tags() {
+        let sample_json = fs::read_to_string(SAMPLE_JSON_PATH).expect("Unable to read the file");
+        let response = serde_json::from_str::<MihoyoResponse>(&sample_json).unwrap();
+        let posts = Vec::<Post>::from(response);
+        assert_eq!(posts[0].tags.as_deref().unwrap(), ["琪亚娜", "草图线稿"]);
+    }
+
+    #[test]
+    fn test_next_page_url() {
+        let sample_json = fs::read_to_string(SAMPLE_JSON_PATH).expect("Unable to read the file");
+        let mut response = serde_json::from_str::<MihoyoResponse>(&sample_json).unwrap();
+        let url = "https://bbs-api.mihoyo.com/post/wapi/getForumPostList?forum_id=4&page_size=20";
+        assert_eq!(
+            response.next_page_url(url).unwrap(),
+            "https://bbs-api.mihoyo.com/post/wapi/getForumPostList?forum_id=4&page_size=20&last_id=36918922"
+        );
+        response.data.is_last = true;
+        assert!(response.next_page_url(url).is_none());
+    }
+
+    #[test]
+    fn test_forum_url() {
+        let url =
+            "https://bbs-api.mihoyo.com/post/wapi/getForumPostList?forum_id=4&gids=1&page_size=20";
+        let forum = ForumSettings {
+            forum_id: 29,
+            gids: 2,
+        };
+        assert_eq!(
+            MihoyoResponse::forum_url(url, &forum).unwrap(),
+            "https://bbs-api.mihoyo.com/post/wapi/getForumPostList?page_size=20&forum_id=29&gids=2"
+        );
+    }
+
+    #[test]
+    fn test_links_follow_the_game() {
+        let sample_json = fs::read_to_string(SAMPLE_JSON_PATH).expect("Unable to read the file");
+        let mut json: serde_json::Value = serde_json::from_str(&sample_json).unwrap();
+        json["data"]["list"][0]["post"]["game_id"] = 2.into();
+        let response = serde_json::from_value::<MihoyoResponse>(json).unwrap();
+        let posts = Vec::<Post>::from(response);
+        assert_eq!(
+            posts[0].post_link,
+            "https://www.miyoushe.com/ys/article/36923914"
+        );
+        assert!(posts[1]
+            .post_link
+            .starts_with("https://www.miyoushe.com/bh3/article/"));
+        assert!(posts[0]
+            .author_link
+            .starts_with("https://www.miyoushe.com/ys/accountCenter/postList?id="));
+    }
+
+    #[test]
+    fn test_posts_of_unknown_games_are_skipped() {
+        let sample_json = fs::read_to_string(SAMPLE_JSON_PATH).expect("Unable to read the file");
+        let mut json: serde_json::Value = serde_json::from_str(&sample_json).unwrap();
+        json["data"]["list"][0]["post"]["game_id"] = 7.into();
+        let response = serde_json::from_value::<MihoyoResponse>(json).unwrap();
+        let posts = Vec::<Post>::from(response);
+        assert_eq!(posts.len(), 19);
+        assert!(posts.iter().all(|p| !p.post_link.ends_with("/36923914")));
+    }
 }
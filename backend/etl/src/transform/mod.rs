@@ -1,8 +1,14 @@
+mod bcy;
+mod hoyolab;
+mod lofter;
 mod mihoyo_bbs;
 mod pixiv;
 mod twitter_home;
 mod twitter_honkai;
 
+pub use bcy::BcyResponse;
+pub use hoyolab::HoyolabResponse;
+pub use lofter::LofterResponse;
 pub use mihoyo_bbs::MihoyoResponse;
 pub use pixiv::PixivResponse;
 use reqwest::Client;
@@ -12,18 +18,98 @@ pub use twitter_honkai::TwitterHonkaiResponse;
 use async_trait::async_trait;
 use backon::ExponentialBuilder;
 use backon::Retryable;
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use std::time::Duration;
+use std::{collections::HashMap, future::Future, pin::Pin, time::Duration};
 
 use crate::Error;
+use config_structs::{ForumSettings, SourceSettings};
 
-#[derive(Serialize, Deserialize, sqlx::Type, Debug)]
+#[derive(Serialize, Deserialize, sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[sqlx(type_name = "post_source", rename_all = "lowercase")]
 pub enum PostSource {
     Twitter,
     Mihoyo,
     Pixiv,
     TwitterHome,
+    Lofter,
+    Bcy,
+    Hoyolab,
+}
+
+/// What the media of a post is, so that clients can render or skip animated posts.
+#[derive(Serialize, Deserialize, sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[sqlx(type_name = "media_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum MediaKind {
+    #[default]
+    Photo,
+    Video,
+    AnimatedGif,
+    /// A Pixiv animation, its frames are listed by `/api/pixiv/:illust_id/ugoira`.
+    Ugoira,
+}
+
+type Request<'a> = Pin<Box<dyn Future<Output = Result<Page, Error>> + Send + 'a>>;
+
+/// A source the scraper can request, built from its [`DataSource`] implementation.
+pub struct Source {
+    /// The key of the source in the `sources` settings.
+    pub id: &'static str,
+    pub post_source: PostSource,
+    request: for<'a> fn(&'a Client, &'a str) -> Request<'a>,
+    backfill_url: fn(&str, NaiveDate, NaiveDate) -> Option<String>,
+    forum_url: fn(&str, &ForumSettings) -> Option<String>,
+}
+
+impl Source {
+    const fn of<T: DataSource + Send + 'static>() -> Self {
+        Self {
+            id: T::ID,
+            post_source: T::POST_SOURCE,
+            request: request::<T>,
+            backfill_url: T::backfill_url,
+            forum_url: T::forum_url,
+        }
+    }
+
+    pub fn request<'a>(&self, client: &'a Client, url: &'a str) -> Request<'a> {
+        (self.request)(client, url)
+    }
+
+    /// See [`DataSource::backfill_url`].
+    pub fn backfill_url(&self, url: &str, since: NaiveDate, until: NaiveDate) -> Option<String> {
+        (self.backfill_url)(url, since, until)
+    }
+
+    /// See [`DataSource::forum_url`].
+    pub fn forum_url(&self, url: &str, forum: &ForumSettings) -> Option<String> {
+        (self.forum_url)(url, forum)
+    }
+}
+
+fn request<'a, T: DataSource + Send + 'static>(client: &'a Client, url: &'a str) -> Request<'a> {
+    T::request_page(client, url)
+}
+
+/// Every source the scraper knows about. A new source only needs an entry
+/// here and a section in the `sources` settings.
+pub const SOURCES: &[Source] = &[
+    Source::of::<PixivResponse>(),
+    Source::of::<TwitterHonkaiResponse>(),
+    Source::of::<MihoyoResponse>(),
+    Source::of::<TwitterHomeResponse>(),
+    Source::of::<LofterResponse>(),
+    Source::of::<BcyResponse>(),
+    Source::of::<HoyolabResponse>(),
+];
+
+/// The sources that are configured and enabled.
+pub fn enabled_sources(settings: &HashMap<String, SourceSettings>) -> Vec<&'static Source> {
+    SOURCES
+        .iter()
+        .filter(|source| settings.get(source.id).is_some_and(|s| s.enabled))
+        .collect()
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -31,32 +117,181 @@ pub struct Post {
     pub author: String,
     pub author_link: String,
     pub author_profile_image: Option<String>,
-    pub created: String,
+    pub created: DateTime<Utc>,
+    /// Every image of the post, the first one being the preview.
+    #[serde(default)]
+    pub images: Vec<PostImage>,
     pub images_number: i32,
+    #[serde(default)]
+    pub media_kind: MediaKind,
     pub post_link: String,
     pub preview_link: String,
     pub source: PostSource,
     pub tags: Option<Vec<String>>,
 }
 
+/// An image of a post, along with its size when the source provides it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PostImage {
+    pub url: String,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    /// The playable files of a video, `url` being its thumbnail.
+    #[serde(default)]
+    pub variants: Vec<MediaVariant>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MediaVariant {
+    pub url: String,
+    pub content_type: String,
+    pub bitrate: Option<i32>,
+}
+
+/// The posts of a single response, along with the URL of the next page.
+pub struct Page {
+    pub posts: Vec<Post>,
+    pub next_url: Option<String>,
+}
+
 #[async_trait]
 pub trait DataSource: Into<Vec<Post>> + DeserializeOwned {
+    /// The key of the source in the `sources` settings.
+    const ID: &'static str;
+    const POST_SOURCE: PostSource;
+
+    /// The URL of the page following this response, which was requested from `url`.
+    fn next_page_url(&self, _url: &str) -> Option<String> {
+        None
+    }
+
+    /// The search `url` narrowed to the posts created from `since` to `until`
+    /// inclusive, or `None` if the source can't search by date.
+    fn backfill_url(_url: &str, _since: NaiveDate, _until: NaiveDate) -> Option<String> {
+        None
+    }
+
+    /// The `url` pointed at another board of the forum, or `None` if the
+    /// source has no boards.
+    fn forum_url(_url: &str, _forum: &ForumSettings) -> Option<String> {
+        None
+    }
+
     #[tracing::instrument(skip(client), level = "trace")]
-    async fn request_and_parse(client: &Client, url: &str) -> Result<Vec<Post>, Error> {
+    async fn request_page(client: &Client, url: &str) -> Result<Page, Error> {
         let response = (|| async { client.get(url).send().await })
             .retry(ExponentialBuilder::default())
             .notify(|err: &reqwest::Error, dur: Duration| {
                 tracing::warn!("retrying {:?} after {:?}", err, dur);
             })
-            .await?;
-        let parsed = response
-            .json::<Self>()
-            .await
-            .map_err(|e| {
-                tracing::error!("Failed to parse a response. Error: {}. URL: {}", e, url);
-                e
-            })?
-            .into();
-        Ok(parsed)
+            .await?
+            .error_for_status()?;
+        let parsed = response.json::<Self>().await.map_err(|e| {
+            tracing::error!("Failed to parse a response. Error: {}. URL: {}", e, url);
+            e
+        })?;
+        let next_url = parsed.next_page_url(url);
+        Ok(Page {
+            posts: parsed.into(),
+            next_url,
+        })
+    }
+}
+
+/// Replaces the given query parameters of the URL.
+fn with_query(mut url: reqwest::Url, params: &[(&str, String)]) -> String {
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| params.iter().all(|(param, _)| key != param))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .extend_pairs(params.iter().map(|(key, value)| (key, value)));
+    url.into()
+}
+
+/// Maps the `type` of a Twitter media.
+fn twitter_media_kind(kind: &str) -> MediaKind {
+    match kind {
+        "video" => MediaKind::Video,
+        "animated_gif" => MediaKind::AnimatedGif,
+        _ => MediaKind::Photo,
+    }
+}
+
+/// Updates the `variables` of a Twitter GraphQL URL.
+fn with_twitter_variables(
+    url: &str,
+    update: impl FnOnce(&mut serde_json::Map<String, serde_json::Value>) -> Option<()>,
+) -> Option<String> {
+    let mut url = reqwest::Url::parse(url).ok()?;
+    let mut pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    let (_, value) = pairs.iter_mut().find(|(key, _)| key == "variables")?;
+    let mut variables: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(value).ok()?;
+    update(&mut variables)?;
+    *value = serde_json::Value::from(variables).to_string();
+    url.query_pairs_mut().clear().extend_pairs(pairs);
+    Some(url.into())
+}
+
+/// Sets the `cursor` in the `variables` of a Twitter GraphQL URL.
+fn with_twitter_cursor(url: &str, cursor: &str) -> Option<String> {
+    with_twitter_variables(url, |variables| {
+        variables.insert("cursor".to_owned(), cursor.into());
+        Some(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(enabled: bool) -> SourceSettings {
+        SourceSettings {
+            enabled,
+            url: "https://example.com".to_owned(),
+            max_pages: 1,
+            page_delay_ms: 0,
+            cron: "0 */20 * * * *".to_owned(),
+            timeout_secs: 30,
+            forums: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_enabled_sources() {
+        let sources = HashMap::from([
+            ("pixiv".to_owned(), settings(true)),
+            ("twitter_home".to_owned(), settings(false)),
+            ("unknown".to_owned(), settings(true)),
+        ]);
+        let enabled: Vec<_> = enabled_sources(&sources)
+            .into_iter()
+            .map(|source| source.post_source)
+            .collect();
+        assert_eq!(enabled, [PostSource::Pixiv]);
+    }
+
+    #[test]
+    fn test_with_twitter_cursor() {
+        let url = "https://x.com/i/api/graphql/id/HomeLatestTimeline?variables=%7B%22count%22%3A20%7D&features=%7B%7D";
+        let next = with_twitter_cursor(url, "DAABCgAB").unwrap();
+        let next = reqwest::Url::parse(&next).unwrap();
+        let pairs: HashMap<_, _> = next.query_pairs().into_owned().collect();
+        assert_eq!(pairs["variables"], r#"{"count":20,"cursor":"DAABCgAB"}"#);
+        assert_eq!(pairs["features"], "{}");
+        assert!(with_twitter_cursor("https://x.com/search?q=1", "DAABCgAB").is_none());
+    }
+
+    #[test]
+    fn test_source_ids_are_unique() {
+        for (i, source) in SOURCES.iter().enumerate() {
+            assert!(SOURCES[i + 1..]
+                .iter()
+                .all(|other| other.id != source.id && other.post_source != source.post_source));
+        }
     }
 }
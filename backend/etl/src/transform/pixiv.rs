@@ -1,9 +1,9 @@
-use chrono::{TimeZone, Utc};
+use chrono::{NaiveDate, TimeZone, Utc};
 use serde::Deserialize;
 
-use crate::transform::{Post, PostSource};
+use crate::transform::{MediaKind, Post, PostImage, PostSource};
 
-use super::DataSource;
+use super::{with_query, DataSource};
 
 #[derive(Deserialize)]
 pub struct PixivResponse {
@@ -13,6 +13,8 @@ pub struct PixivResponse {
 #[derive(Deserialize)]
 struct PixivBody {
     illusts: Vec<PixivIllust>,
+    #[serde(rename = "lastPage")]
+    last_page: Option<u32>,
 }
 
 #[derive(Deserialize)]
@@ -23,6 +25,11 @@ struct PixivIllust {
     id: String,
     user_id: String,
     page_count: String,
+    width: String,
+    height: String,
+    /// `"2"` for ugoira.
+    #[serde(rename = "type")]
+    kind: String,
     author_details: AuthorDetails,
 }
 
@@ -33,10 +40,17 @@ struct AuthorDetails {
 
 impl From<PixivIllust> for Post {
     fn from(value: PixivIllust) -> Self {
-        let created = Utc
-            .timestamp_opt(value.upload_timestamp, 0)
-            .unwrap()
-            .to_rfc3339();
+        let created = Utc.timestamp_opt(value.upload_timestamp, 0).unwrap();
+        let images_number = value.page_count.parse().unwrap();
+        // The search only lists the first page, the others differ by the page index.
+        let images = (0..images_number)
+            .map(|page| PostImage {
+                url: value.url.replace("_p0_", &format!("_p{page}_")),
+                width: (page == 0).then(|| value.width.parse().ok()).flatten(),
+                height: (page == 0).then(|| value.height.parse().ok()).flatten(),
+                variants: Vec::new(),
+            })
+            .collect();
         Self {
             preview_link: value.url,
             post_link: format!("https://www.pixiv.net/en/artworks/{}", value.id),
@@ -44,7 +58,12 @@ impl From<PixivIllust> for Post {
             author: value.author_details.user_name,
             created,
             source: PostSource::Pixiv,
-            images_number: value.page_count.parse().unwrap(),
+            images,
+            images_number,
+            media_kind: match value.kind.as_str() {
+                "2" => MediaKind::Ugoira,
+                _ => MediaKind::Photo,
+            },
             tags: Some(value.tags),
             author_profile_image: None,
         }
@@ -57,7 +76,40 @@ impl From<PixivResponse> for Vec<Post> {
     }
 }
 
-impl DataSource for PixivResponse {}
+impl DataSource for PixivResponse {
+    const ID: &'static str = "pixiv";
+    const POST_SOURCE: PostSource = PostSource::Pixiv;
+
+    /// Pixiv search pages are numbered by the `p` query parameter, starting at 1.
+    fn next_page_url(&self, url: &str) -> Option<String> {
+        if self.body.illusts.is_empty() {
+            return None;
+        }
+        let url = reqwest::Url::parse(url).ok()?;
+        let page: u32 = url
+            .query_pairs()
+            .find(|(key, _)| key == "p")
+            .and_then(|(_, value)| value.parse().ok())
+            .unwrap_or(1);
+        if self.body.last_page.is_some_and(|last| page >= last) {
+            return None;
+        }
+        Some(with_query(url, &[("p", (page + 1).to_string())]))
+    }
+
+    /// Pixiv filters the search by the `scd` and `ecd` creation dates.
+    fn backfill_url(url: &str, since: NaiveDate, until: NaiveDate) -> Option<String> {
+        let url = reqwest::Url::parse(url).ok()?;
+        Some(with_query(
+            url,
+            &[
+                ("scd", since.to_string()),
+                ("ecd", until.to_string()),
+                ("p", "1".to_owned()),
+            ],
+        ))
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -72,4 +124,47 @@ mod tests {
         let sample_json = fs::read_to_string(PIXIV_JSON_PATH).expect("Unable to read the file");
         serde_json::from_str::<PixivResponse>(&sample_json).unwrap();
     }
+
+    #[test]
+    fn test_every_page_is_an_image() {
+        let sample_json = fs::read_to_string(PIXIV_JSON_PATH).expect("Unable to read the file");
+        let response = serde_json::from_str::<PixivResponse>(&sample_json).unwrap();
+        let post = Vec::<Post>::from(response).remove(0);
+        assert_eq!(post.images.len(), 7);
+        assert_eq!(post.images[0].url, post.preview_link);
+        assert_eq!(post.images[0].width, Some(1920));
+        assert!(post.images[6].url.contains("106302935_p6_master1200"));
+        assert_eq!(post.images[6].width, None);
+    }
+
+    #[test]
+    fn test_ugoira_are_animated() {
+        let sample_json = fs::read_to_string(PIXIV_JSON_PATH).expect("Unable to read the file");
+        let response = serde_json::from_str::<PixivResponse>(&sample_json).unwrap();
+        let posts = Vec::<Post>::from(response);
+        assert!(posts.iter().any(|p| p.media_kind == MediaKind::Ugoira));
+        assert!(posts.iter().any(|p| p.media_kind == MediaKind::Photo));
+    }
+
+    #[test]
+    fn test_next_page_url_increments_the_page() {
+        let sample_json = fs::read_to_string(PIXIV_JSON_PATH).expect("Unable to read the file");
+        let response = serde_json::from_str::<PixivResponse>(&sample_json).unwrap();
+        let url = "https://www.pixiv.net/touch/ajax/search/illusts?word=honkai&lang=en";
+        let second = response.next_page_url(url).unwrap();
+        assert!(second.ends_with("word=honkai&lang=en&p=2"));
+        let third = response.next_page_url(&second).unwrap();
+        assert!(third.ends_with("word=honkai&lang=en&p=3"));
+        let last = "https://www.pixiv.net/touch/ajax/search/illusts?word=honkai&p=1000";
+        assert!(response.next_page_url(last).is_none());
+    }
+
+    #[test]
+    fn test_backfill_url_searches_the_window() {
+        let url = "https://www.pixiv.net/touch/ajax/search/illusts?word=honkai&p=3";
+        let since = NaiveDate::from_ymd_opt(2026, 9, 1).unwrap();
+        let until = NaiveDate::from_ymd_opt(2026, 9, 10).unwrap();
+        let backfill = PixivResponse::backfill_url(url, since, until).unwrap();
+        assert!(backfill.ends_with("word=honkai&scd=2026-09-01&ecd=2026-09-10&p=1"));
+    }
 }
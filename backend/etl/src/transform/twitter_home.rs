@@ -1,13 +1,13 @@
 use async_trait::async_trait;
-use chrono::DateTime;
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
 
 use crate::{
-    transform::{Post, PostSource},
+    transform::{MediaVariant, Post, PostImage, PostSource},
     Error,
 };
 
-use super::DataSource;
+use super::{twitter_media_kind, with_twitter_cursor, DataSource};
 
 #[derive(Deserialize)]
 pub struct TwitterHomeResponse {
@@ -31,7 +31,10 @@ pub struct HomeTimelineUrt {
 
 #[derive(Deserialize)]
 pub struct Instruction {
+    #[serde(default)]
     pub entries: Vec<Entry>,
+    /// Set by the `TimelineReplaceEntry` instructions of the following pages.
+    pub entry: Option<Entry>,
 }
 
 #[derive(Deserialize)]
@@ -45,8 +48,14 @@ pub struct Entry {
 #[serde(untagged)]
 pub enum Content {
     SingleTweet(SingleTweet),
-    Conversation { items: Vec<ConversationItem> },
-    Cursor { value: String },
+    Conversation {
+        items: Vec<ConversationItem>,
+    },
+    Cursor {
+        value: String,
+        #[serde(rename = "cursorType")]
+        cursor_type: String,
+    },
 }
 
 #[derive(Deserialize)]
@@ -132,6 +141,28 @@ pub struct Entities {
 pub struct Media {
     pub expanded_url: String,
     pub media_url_https: String,
+    pub original_info: Option<OriginalInfo>,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub video_info: Option<VideoInfo>,
+}
+
+#[derive(Deserialize)]
+pub struct VideoInfo {
+    pub variants: Vec<Variant>,
+}
+
+#[derive(Deserialize)]
+pub struct Variant {
+    pub bitrate: Option<i32>,
+    pub content_type: String,
+    pub url: String,
+}
+
+#[derive(Deserialize)]
+pub struct OriginalInfo {
+    pub width: i32,
+    pub height: i32,
 }
 
 impl TryFrom<Tweet> for Post {
@@ -143,7 +174,7 @@ impl TryFrom<Tweet> for Post {
         let created = DateTime::parse_from_str(&value.created_at, "%a %b %d %H:%M:%S %z %Y")
             .ok()
             .ok_or(Error::Parsing)?
-            .to_rfc3339();
+            .with_timezone(&Utc);
         let media = value
             .entities
             .media
@@ -153,12 +184,34 @@ impl TryFrom<Tweet> for Post {
             .ok_or(Error::Parsing)?;
         Ok(Self {
             preview_link: main_pic.media_url_https.to_string(),
-            post_link: main_pic.expanded_url.replace("/photo/1", ""),
+            post_link: main_pic
+                .expanded_url
+                .replace("/photo/1", "")
+                .replace("/video/1", ""),
             author_link: format!("https://twitter.com/{}", user.core.screen_name),
             author: format!("{}@{}", user.core.name, user.core.screen_name),
             created,
             source: PostSource::TwitterHome,
+            images: media
+                .iter()
+                .map(|media| PostImage {
+                    url: media.media_url_https.clone(),
+                    width: media.original_info.as_ref().map(|info| info.width),
+                    height: media.original_info.as_ref().map(|info| info.height),
+                    variants: media
+                        .video_info
+                        .iter()
+                        .flat_map(|info| &info.variants)
+                        .map(|variant| MediaVariant {
+                            url: variant.url.clone(),
+                            content_type: variant.content_type.clone(),
+                            bitrate: variant.bitrate,
+                        })
+                        .collect(),
+                })
+                .collect(),
             images_number: media.len() as i32,
+            media_kind: twitter_media_kind(&main_pic.kind),
             tags: None,
             author_profile_image: Some(user.avatar.image_url),
         })
@@ -184,7 +237,7 @@ impl From<TwitterHomeResponse> for Vec<Post> {
                             .into_iter()
                             .map(|item| process_tweet(item.item))
                             .collect::<Vec<_>>(),
-                        Content::Cursor { value: _ } => vec![None],
+                        Content::Cursor { .. } => vec![None],
                     })
             })
             .flatten()
@@ -206,7 +259,25 @@ fn process_tweet(tweet: SingleTweet) -> Option<Post> {
 }
 
 #[async_trait]
-impl DataSource for TwitterHomeResponse {}
+impl DataSource for TwitterHomeResponse {
+    const ID: &'static str = "twitter_home";
+    const POST_SOURCE: PostSource = PostSource::TwitterHome;
+
+    fn next_page_url(&self, url: &str) -> Option<String> {
+        let cursor = self
+            .data
+            .home
+            .home_timeline_urt
+            .instructions
+            .iter()
+            .flat_map(|instruction| instruction.entries.iter().chain(&instruction.entry))
+            .find_map(|entry| match &entry.content {
+                Content::Cursor { value, cursor_type } if cursor_type == "Bottom" => Some(value),
+                _ => None,
+            })?;
+        with_twitter_cursor(url, cursor)
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -214,6 +285,8 @@ mod tests {
 
     use std::fs;
 
+    use crate::transform::MediaKind;
+
     const SAMPLE_JSON_PATH: &str = "../tests/assets/json/twitter-home.json";
 
     #[test]
@@ -224,4 +297,57 @@ mod tests {
 
         result.unwrap();
     }
+
+    #[test]
+    fn test_next_page_url_follows_the_bottom_cursor() {
+        let sample_json = fs::read_to_string(SAMPLE_JSON_PATH).expect("Unable to read the file");
+        let response: TwitterHomeResponse = serde_json::from_str(&sample_json).unwrap();
+        let next_url = response
+            .next_page_url("https://x.com/i/api/graphql/id/HomeLatestTimeline?variables=%7B%22count%22%3A20%7D")
+            .unwrap();
+        let next_url = reqwest::Url::parse(&next_url).unwrap();
+        let (_, variables) = next_url.query_pairs().next().unwrap();
+        let variables: serde_json::Value = serde_json::from_str(&variables).unwrap();
+        assert!(variables["cursor"]
+            .as_str()
+            .unwrap()
+            .starts_with("DAABCgABHPqlRsD__6c"));
+    }
+
+    #[test]
+    fn test_every_media_is_an_image() {
+        let sample_json = fs::read_to_string(SAMPLE_JSON_PATH).expect("Unable to read the file");
+        let response: TwitterHomeResponse = serde_json::from_str(&sample_json).unwrap();
+        let posts = Vec::<Post>::from(response);
+        assert!(posts.iter().any(|p| p.images_number > 1));
+        for post in posts {
+            assert_eq!(post.images.len(), post.images_number as usize);
+            assert_eq!(post.images[0].url, post.preview_link);
+            assert!(post.images.iter().all(|image| image.width.is_some()));
+        }
+    }
+
+    #[test]
+    fn test_videos_have_variants() {
+        let sample_json = fs::read_to_string(SAMPLE_JSON_PATH).expect("Unable to read the file");
+        let response: serde_json::Value = serde_json::from_str(&sample_json).unwrap();
+        // The videos of the sample are retweets, which the feed skips.
+        let entry = response["data"]["home"]["home_timeline_urt"]["instructions"][0]["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["entryId"] == "tweet-2088162418119836158")
+            .unwrap();
+        let tweet: Tweet = serde_json::from_value(
+            entry["content"]["itemContent"]["tweet_results"]["result"].clone(),
+        )
+        .unwrap();
+        let video = Post::try_from(tweet).unwrap();
+        assert_eq!(video.media_kind, MediaKind::Video);
+        assert!(!video.post_link.ends_with("/video/1"));
+        assert!(video.images[0]
+            .variants
+            .iter()
+            .any(|v| v.content_type == "video/mp4" && v.bitrate.is_some()));
+    }
 }
@@ -1,12 +1,12 @@
-use chrono::DateTime;
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    transform::{Post, PostSource},
+    transform::{MediaVariant, Post, PostImage, PostSource},
     Error,
 };
 
-use super::DataSource;
+use super::{twitter_media_kind, with_twitter_cursor, with_twitter_variables, DataSource};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -43,7 +43,10 @@ pub struct Timeline {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Instruction {
+    #[serde(default)]
     pub entries: Vec<Entry>,
+    /// Set by the `TimelineReplaceEntry` instructions of the following pages.
+    pub entry: Option<Entry>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -59,7 +62,8 @@ pub enum Content {
     #[allow(non_snake_case)]
     Tweet { itemContent: ItemContent },
     #[serde(rename = "TimelineTimelineCursor")]
-    Cursor,
+    #[allow(non_snake_case)]
+    Cursor { value: String, cursorType: String },
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -148,6 +152,30 @@ pub struct Media {
     pub expanded_url: String,
     #[serde(rename = "media_url_https")]
     pub media_url_https: String,
+    #[serde(rename = "original_info")]
+    pub original_info: Option<OriginalInfo>,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(rename = "video_info")]
+    pub video_info: Option<VideoInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoInfo {
+    pub variants: Vec<Variant>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Variant {
+    pub bitrate: Option<i32>,
+    pub content_type: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OriginalInfo {
+    pub width: i32,
+    pub height: i32,
 }
 
 impl TryFrom<Tweet> for Post {
@@ -159,7 +187,7 @@ impl TryFrom<Tweet> for Post {
         let created = DateTime::parse_from_str(&value.created_at, "%a %b %d %H:%M:%S %z %Y")
             .ok()
             .ok_or(Error::Parsing)?
-            .to_rfc3339();
+            .with_timezone(&Utc);
         let media = value
             .entities
             .media
@@ -169,12 +197,34 @@ impl TryFrom<Tweet> for Post {
             .ok_or(Error::Parsing)?;
         Ok(Self {
             preview_link: main_pic.media_url_https.to_string(),
-            post_link: main_pic.expanded_url.replace("/photo/1", ""),
+            post_link: main_pic
+                .expanded_url
+                .replace("/photo/1", "")
+                .replace("/video/1", ""),
             author_link: format!("https://twitter.com/{}", user.core.screen_name),
             author: format!("{}@{}", user.core.name, user.core.screen_name),
             created,
             source: PostSource::Twitter,
+            images: media
+                .iter()
+                .map(|media| PostImage {
+                    url: media.media_url_https.clone(),
+                    width: media.original_info.as_ref().map(|info| info.width),
+                    height: media.original_info.as_ref().map(|info| info.height),
+                    variants: media
+                        .video_info
+                        .iter()
+                        .flat_map(|info| &info.variants)
+                        .map(|variant| MediaVariant {
+                            url: variant.url.clone(),
+                            content_type: variant.content_type.clone(),
+                            bitrate: variant.bitrate,
+                        })
+                        .collect(),
+                })
+                .collect(),
             images_number: media.len() as i32,
+            media_kind: twitter_media_kind(&main_pic.kind),
             tags: None,
             author_profile_image: Some(user.avatar.image_url),
         })
@@ -213,7 +263,38 @@ impl From<TwitterHonkaiResponse> for Vec<Post> {
     }
 }
 
-impl DataSource for TwitterHonkaiResponse {}
+impl DataSource for TwitterHonkaiResponse {
+    const ID: &'static str = "twitter_honkai";
+    const POST_SOURCE: PostSource = PostSource::Twitter;
+
+    fn next_page_url(&self, url: &str) -> Option<String> {
+        let cursor = self
+            .data
+            .search_by_raw_query
+            .search_timeline
+            .timeline
+            .instructions
+            .iter()
+            .flat_map(|instruction| instruction.entries.iter().chain(&instruction.entry))
+            .find_map(|entry| match &entry.content {
+                Content::Cursor { value, cursorType } if cursorType == "Bottom" => Some(value),
+                _ => None,
+            })?;
+        with_twitter_cursor(url, cursor)
+    }
+
+    /// Twitter search takes the window as `since:` and `until:` operators,
+    /// the latter being exclusive.
+    fn backfill_url(url: &str, since: NaiveDate, until: NaiveDate) -> Option<String> {
+        with_twitter_variables(url, |variables| {
+            let query = variables.get("rawQuery")?.as_str()?;
+            let query = format!("{query} since:{since} until:{}", until.succ_opt()?);
+            variables.insert("rawQuery".to_owned(), query.into());
+            variables.remove("cursor");
+            Some(())
+        })
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -231,4 +312,37 @@ mod tests {
 
         result.unwrap();
     }
+
+    #[test]
+    fn test_next_page_url_follows_the_bottom_cursor() {
+        let sample_json = fs::read_to_string(SAMPLE_JSON_PATH).expect("Unable to read the file");
+        let response: TwitterHonkaiResponse = serde_json::from_str(&sample_json).unwrap();
+        let next_url = response
+            .next_page_url(
+                "https://x.com/i/api/graphql/id/SearchTimeline?variables=%7B%22count%22%3A20%7D",
+            )
+            .unwrap();
+        let next_url = reqwest::Url::parse(&next_url).unwrap();
+        let (_, variables) = next_url.query_pairs().next().unwrap();
+        let variables: serde_json::Value = serde_json::from_str(&variables).unwrap();
+        assert!(variables["cursor"]
+            .as_str()
+            .unwrap()
+            .starts_with("DAADDAABCgABHPuG2I9bQac"));
+    }
+
+    #[test]
+    fn test_backfill_url_adds_the_window_to_the_query() {
+        let url = "https://x.com/i/api/graphql/id/SearchTimeline?variables=%7B%22rawQuery%22%3A%22%23Honkaiimpact3rd%22%7D";
+        let since = NaiveDate::from_ymd_opt(2026, 9, 1).unwrap();
+        let until = NaiveDate::from_ymd_opt(2026, 9, 10).unwrap();
+        let url = TwitterHonkaiResponse::backfill_url(url, since, until).unwrap();
+        let url = reqwest::Url::parse(&url).unwrap();
+        let (_, variables) = url.query_pairs().next().unwrap();
+        let variables: serde_json::Value = serde_json::from_str(&variables).unwrap();
+        assert_eq!(
+            variables["rawQuery"],
+            "#Honkaiimpact3rd since:2026-09-01 until:2026-09-11"
+        );
+    }
 }
@@ -0,0 +1,2 @@
+-- Keyset pagination walks honkai_posts by id
+CREATE UNIQUE INDEX honkai_posts_id_idx ON honkai_posts (id);
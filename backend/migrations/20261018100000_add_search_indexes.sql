@@ -0,0 +1,9 @@
+-- Text searched by /api/search, wrapped in a function since indexes need an IMMUTABLE expression
+CREATE FUNCTION honkai_posts_search_document(author TEXT, tags TEXT[]) RETURNS tsvector
+    LANGUAGE sql IMMUTABLE PARALLEL SAFE
+    AS $$ SELECT to_tsvector('simple', author || ' ' || coalesce(array_to_string(tags, ' '), '')) $$;
+
+CREATE INDEX honkai_posts_search_idx ON honkai_posts
+    USING GIN (honkai_posts_search_document(author, tags));
+CREATE INDEX honkai_posts_tags_idx ON honkai_posts USING GIN (tags);
+CREATE INDEX honkai_posts_author_idx ON honkai_posts (author);
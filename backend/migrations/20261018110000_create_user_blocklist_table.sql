@@ -0,0 +1,10 @@
+-- Authors and tags hidden by a user, replaces the filterList kept in the browser
+CREATE TYPE block_kind AS ENUM ('author', 'tag');
+CREATE TABLE user_blocklist (
+    user_key TEXT NOT NULL,
+    kind block_kind NOT NULL,
+    value TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_key, kind, value)
+);
+CREATE INDEX user_blocklist_value_idx ON user_blocklist (kind, value);
@@ -0,0 +1,10 @@
+-- Global blacklist managed through the admin API, applied on top of the configured one
+CREATE TYPE blacklist_match AS ENUM ('exact', 'case_insensitive', 'regex');
+CREATE TABLE blacklist (
+    id SERIAL PRIMARY KEY,
+    kind block_kind NOT NULL,
+    pattern TEXT NOT NULL,
+    match_mode blacklist_match NOT NULL DEFAULT 'exact',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (kind, pattern, match_mode)
+);
@@ -0,0 +1,2 @@
+-- Posts hidden by a blacklist purge stay in the table but are no longer served
+ALTER TABLE honkai_posts ADD COLUMN hidden_at TIMESTAMPTZ NULL;
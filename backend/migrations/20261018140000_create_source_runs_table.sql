@@ -0,0 +1,14 @@
+-- One row per source per scrape, so failing sources don't go unnoticed
+CREATE TABLE source_runs(
+    id SERIAL PRIMARY KEY,
+    source post_source NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL,
+    finished_at TIMESTAMPTZ NOT NULL,
+    success BOOLEAN NOT NULL,
+    error_kind TEXT NULL,
+    error TEXT NULL,
+    post_count INTEGER NOT NULL,
+    duration_ms INTEGER NOT NULL
+);
+
+CREATE INDEX source_runs_source_finished_at_idx ON source_runs (source, finished_at DESC);
@@ -0,0 +1,2 @@
+-- Update times are derived from the successful source_runs now
+DROP TABLE last_update_time;
@@ -0,0 +1,16 @@
+-- One row per fill_db invocation, the per-source outcomes reference it
+CREATE TABLE scrape_runs(
+    id SERIAL PRIMARY KEY,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ NULL,
+    error TEXT NULL
+);
+
+ALTER TABLE source_runs RENAME COLUMN post_count TO fetched_count;
+ALTER TABLE source_runs
+    ADD COLUMN run_id INTEGER NULL REFERENCES scrape_runs (id) ON DELETE CASCADE,
+    ADD COLUMN new_count INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN duplicate_count INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN blacklisted_count INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX source_runs_run_id_idx ON source_runs (run_id);
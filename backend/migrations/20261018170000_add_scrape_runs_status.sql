@@ -0,0 +1,20 @@
+-- Runs can be requested over HTTP, they stay pending until the scraper picks them up
+CREATE TYPE scrape_status AS ENUM ('pending', 'running', 'finished', 'failed');
+
+ALTER TABLE scrape_runs
+    ADD COLUMN status scrape_status NOT NULL DEFAULT 'running',
+    -- NULL means every source
+    ADD COLUMN sources post_source[] NULL,
+    ADD COLUMN requested_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    ALTER COLUMN started_at DROP NOT NULL,
+    ALTER COLUMN started_at DROP DEFAULT;
+
+UPDATE scrape_runs
+SET requested_at = started_at,
+    status = CASE
+        WHEN finished_at IS NOT NULL AND error IS NULL THEN 'finished'::scrape_status
+        ELSE 'failed'::scrape_status
+    END;
+
+-- Requests made while a run is pending are merged into it
+CREATE UNIQUE INDEX scrape_runs_single_pending_idx ON scrape_runs (status) WHERE status = 'pending';
@@ -0,0 +1,19 @@
+-- Progress of the historical backfills, so an interrupted one can be resumed
+CREATE TABLE backfills(
+    id SERIAL PRIMARY KEY,
+    source post_source NOT NULL,
+    since DATE NOT NULL,
+    until DATE NOT NULL,
+    next_url TEXT NULL,
+    pages INTEGER NOT NULL DEFAULT 0,
+    fetched_count INTEGER NOT NULL DEFAULT 0,
+    new_count INTEGER NOT NULL DEFAULT 0,
+    duplicate_count INTEGER NOT NULL DEFAULT 0,
+    blacklisted_count INTEGER NOT NULL DEFAULT 0,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ NULL
+);
+
+CREATE UNIQUE INDEX backfills_single_unfinished_idx ON backfills (source, since, until)
+    WHERE finished_at IS NULL;
@@ -0,0 +1,14 @@
+-- Every image of a post, in the order the source lists them
+CREATE TABLE post_images(
+    id SERIAL PRIMARY KEY,
+    post_id INTEGER NOT NULL REFERENCES honkai_posts (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    width INTEGER NULL,
+    height INTEGER NULL,
+    UNIQUE (post_id, position)
+);
+
+-- Only the preview is known of the posts stored so far
+INSERT INTO post_images (post_id, position, url)
+SELECT id, 0, preview_link FROM honkai_posts;
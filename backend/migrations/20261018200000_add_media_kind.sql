@@ -0,0 +1,6 @@
+CREATE TYPE media_kind AS ENUM ('photo', 'video', 'animated_gif', 'ugoira');
+
+ALTER TABLE honkai_posts ADD COLUMN media_kind media_kind NOT NULL DEFAULT 'photo';
+
+-- The playable files of a video, the url of the image being its thumbnail
+ALTER TABLE post_images ADD COLUMN variants JSONB NOT NULL DEFAULT '[]';
@@ -0,0 +1,4 @@
+-- The transforms stored RFC 3339 strings
+ALTER TABLE honkai_posts ALTER COLUMN created TYPE TIMESTAMPTZ USING created::TIMESTAMPTZ;
+
+CREATE INDEX honkai_posts_created_idx ON honkai_posts (created DESC, id DESC);
@@ -0,0 +1,8 @@
+-- Perceptual hashes of the previews, to group the same artwork posted on several sources
+ALTER TABLE honkai_posts
+    ADD COLUMN preview_hash BIGINT NULL,
+    ADD COLUMN preview_hashed_at TIMESTAMPTZ NULL,
+    ADD COLUMN duplicate_of INTEGER NULL REFERENCES honkai_posts (id) ON DELETE SET NULL;
+
+CREATE INDEX honkai_posts_duplicate_of_idx ON honkai_posts (duplicate_of);
+CREATE INDEX honkai_posts_unhashed_idx ON honkai_posts (id) WHERE preview_hashed_at IS NULL;
@@ -0,0 +1,5 @@
+-- Set when a scrape refreshes the metadata of a stored post
+ALTER TABLE honkai_posts ADD COLUMN updated_at TIMESTAMPTZ NULL;
+
+ALTER TABLE source_runs ADD COLUMN updated_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE backfills ADD COLUMN updated_count INTEGER NOT NULL DEFAULT 0;
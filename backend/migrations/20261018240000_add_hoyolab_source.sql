@@ -0,0 +1 @@
+ALTER TYPE post_source ADD VALUE 'hoyolab';
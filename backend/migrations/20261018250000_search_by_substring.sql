@@ -0,0 +1,13 @@
+-- The words of CJK titles and tags are not separated by spaces, so the free
+-- text search matches substrings through trigrams instead of a tsvector
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+DROP INDEX honkai_posts_search_idx;
+DROP FUNCTION honkai_posts_search_document(TEXT, TEXT[]);
+
+CREATE FUNCTION honkai_posts_search_text(author TEXT, tags TEXT[]) RETURNS TEXT
+    LANGUAGE sql IMMUTABLE PARALLEL SAFE
+    AS $$ SELECT author || ' ' || coalesce(array_to_string(tags, ' '), '') $$;
+
+CREATE INDEX honkai_posts_search_idx ON honkai_posts
+    USING GIN (honkai_posts_search_text(author, tags) gin_trgm_ops);
@@ -0,0 +1,3 @@
+-- The posts a new preview is compared with, looked up by their creation time
+CREATE INDEX honkai_posts_dedup_candidates_idx ON honkai_posts (created)
+    WHERE preview_hash IS NOT NULL AND duplicate_of IS NULL AND hidden_at IS NULL;
@@ -0,0 +1,2 @@
+-- Previews that failed to download are retried a few times before giving up
+ALTER TABLE honkai_posts ADD COLUMN preview_hash_attempts INTEGER NOT NULL DEFAULT 0;
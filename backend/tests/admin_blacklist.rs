@@ -0,0 +1,163 @@
+use serde_json::{json, Value};
+
+use etl::{load_blacklist, PostSource};
+use tests::{insert_post, sample_post, spawn_api};
+
+const ADMIN_TOKEN: &str = "Token test-admin-token";
+
+#[tokio::test]
+async fn test_admin_blacklist_requires_the_admin_token() {
+    let app = spawn_api().await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/admin/blacklist", app.addr);
+
+    let response = client.get(&url).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = client
+        .get(&url)
+        .header("authorization", "Token wrong")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn test_admin_blacklist_crud_is_applied_by_the_scraper() {
+    let app = spawn_api().await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/admin/blacklist", app.addr);
+
+    let response = client
+        .post(&url)
+        .header("authorization", ADMIN_TOKEN)
+        .json(&json!({"kind": "tag", "pattern": "koikatsu", "match_mode": "case_insensitive"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 201);
+    let entry = response.json::<Value>().await.unwrap();
+    assert_eq!(entry["match_mode"], "case_insensitive");
+
+    let duplicate = client
+        .post(&url)
+        .header("authorization", ADMIN_TOKEN)
+        .json(&json!({"kind": "tag", "pattern": "koikatsu", "match_mode": "case_insensitive"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(duplicate.status().as_u16(), 422);
+
+    let invalid = client
+        .post(&url)
+        .header("authorization", ADMIN_TOKEN)
+        .json(&json!({"kind": "author", "pattern": "[unclosed", "match_mode": "regex"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(invalid.status().as_u16(), 422);
+
+    let mut post = sample_post("https://example.com/1", PostSource::Pixiv);
+    post.tags = Some(vec!["KoiKatsu".to_owned()]);
+    let matcher = load_blacklist(&app.scraper_state.db_pool, &app.scraper_state.blacklist)
+        .await
+        .unwrap();
+    assert!(matcher.matches(&post));
+
+    let entry_url = format!("{}/{}", url, entry["id"]);
+    let response = client
+        .put(&entry_url)
+        .header("authorization", ADMIN_TOKEN)
+        .json(&json!({"kind": "tag", "pattern": "koikatsu"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    let matcher = load_blacklist(&app.scraper_state.db_pool, &app.scraper_state.blacklist)
+        .await
+        .unwrap();
+    assert!(!matcher.matches(&post));
+
+    let response = client
+        .delete(&entry_url)
+        .header("authorization", ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 204);
+    let entries = client
+        .get(&url)
+        .header("authorization", ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap()
+        .json::<Vec<Value>>()
+        .await
+        .unwrap();
+    assert!(entries.is_empty());
+}
+
+#[tokio::test]
+async fn test_purge_hides_stored_posts_matching_the_blacklist() {
+    let app = spawn_api().await;
+    let client = reqwest::Client::new();
+    let mut blacklisted = sample_post("https://example.com/1", PostSource::Pixiv);
+    blacklisted.tags = Some(vec!["MMD".to_owned()]);
+    insert_post(&app.api_state.db_pool, &blacklisted).await;
+    insert_post(
+        &app.api_state.db_pool,
+        &sample_post("https://example.com/2", PostSource::Pixiv),
+    )
+    .await;
+    // Blocks of users stay out of the purge.
+    let mut user_blocked = sample_post("https://example.com/3", PostSource::Pixiv);
+    user_blocked.author = "Blocked".to_owned();
+    insert_post(&app.api_state.db_pool, &user_blocked).await;
+    let response = client
+        .post(format!("{}/api/blocklist", app.addr))
+        .header("x-user-key", "user-a")
+        .json(&json!({"kind": "author", "value": "Blocked"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 204);
+
+    let purge = |dry_run: bool| {
+        client
+            .post(format!(
+                "{}/api/admin/blacklist/purge?dry_run={}",
+                app.addr, dry_run
+            ))
+            .header("authorization", ADMIN_TOKEN)
+            .send()
+    };
+
+    let report = purge(true).await.unwrap().json::<Value>().await.unwrap();
+    assert_eq!(
+        report,
+        json!({"scanned": 3, "matched": 1, "hidden": 0, "dry_run": true})
+    );
+
+    let report = purge(false).await.unwrap().json::<Value>().await.unwrap();
+    assert_eq!(
+        report,
+        json!({"scanned": 3, "matched": 1, "hidden": 1, "dry_run": false})
+    );
+
+    let posts = client
+        .get(format!("{}/api/honkai", app.addr))
+        .send()
+        .await
+        .unwrap()
+        .json::<Value>()
+        .await
+        .unwrap();
+    let links: Vec<&str> = posts["posts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|post| post["post_link"].as_str().unwrap())
+        .collect();
+    assert_eq!(links, ["https://example.com/3", "https://example.com/2"]);
+}
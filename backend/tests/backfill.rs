@@ -0,0 +1,100 @@
+use std::fs;
+
+use chrono::NaiveDate;
+use wiremock::matchers::{path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+use etl::backfill;
+use tests::{spawn_api, TestApi};
+
+const EMPTY_PAGE: &str = r#"{"body": {"illusts": [], "lastPage": 2}}"#;
+
+async fn mount_page(app: &TestApi, page: &str, response: ResponseTemplate) {
+    Mock::given(path("/pixiv"))
+        .and(query_param("p", page))
+        .and(query_param("scd", "2026-09-01"))
+        .and(query_param("ecd", "2026-09-10"))
+        .respond_with(response)
+        .mount(&app.mock_server)
+        .await;
+}
+
+fn json(body: String) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_raw(body, "application/json")
+}
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 9, day).unwrap()
+}
+
+async fn requested_pages(app: &TestApi) -> Vec<String> {
+    app.mock_server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter_map(|r| {
+            r.url
+                .query_pairs()
+                .find(|(key, _)| key == "p")
+                .map(|(_, page)| page.into_owned())
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_backfill_pages_through_the_window() {
+    let app = spawn_api().await;
+    let pixiv = fs::read_to_string("assets/json/pixiv.json").expect("Unable to read the file");
+    mount_page(&app, "1", json(pixiv)).await;
+    mount_page(&app, "2", json(EMPTY_PAGE.to_owned())).await;
+
+    let backfill = backfill(&app.scraper_state, "pixiv", date(1), date(10))
+        .await
+        .unwrap();
+
+    assert_eq!(requested_pages(&app).await, ["1", "2"]);
+    assert_eq!(backfill.pages, 2);
+    assert!(backfill.finished_at.is_some());
+    assert!(backfill.new_count > 0);
+    let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM honkai_posts")
+        .fetch_one(&app.api_state.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(stored, backfill.new_count as i64);
+}
+
+#[tokio::test]
+async fn test_interrupted_backfill_is_resumed() {
+    let app = spawn_api().await;
+    let pixiv = fs::read_to_string("assets/json/pixiv.json").expect("Unable to read the file");
+    mount_page(&app, "1", json(pixiv)).await;
+    mount_page(&app, "2", ResponseTemplate::new(500)).await;
+
+    assert!(backfill(&app.scraper_state, "pixiv", date(1), date(10))
+        .await
+        .is_err());
+
+    app.mock_server.reset().await;
+    mount_page(&app, "2", json(EMPTY_PAGE.to_owned())).await;
+    let backfill = backfill(&app.scraper_state, "pixiv", date(1), date(10))
+        .await
+        .unwrap();
+
+    assert_eq!(requested_pages(&app).await, ["2"]);
+    assert_eq!(backfill.pages, 2);
+    assert!(backfill.finished_at.is_some());
+}
+
+#[tokio::test]
+async fn test_backfill_needs_a_date_search() {
+    let app = spawn_api().await;
+    assert!(
+        backfill(&app.scraper_state, "twitter_home", date(1), date(10))
+            .await
+            .is_err()
+    );
+    assert!(backfill(&app.scraper_state, "pixiv", date(10), date(1))
+        .await
+        .is_err());
+}
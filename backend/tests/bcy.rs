@@ -0,0 +1,31 @@
+use etl::PostSource;
+use tests::{get_posts, scrape_recorded, spawn_api};
+
+#[tokio::test]
+async fn test_bcy_posts_are_saved() {
+    let mut app = spawn_api().await;
+    // Disabled by default, since BCY itself is closed.
+    app.scraper_state.sources.get_mut("bcy").unwrap().enabled = true;
+    let posts = scrape_recorded(&app, "bcy", PostSource::Bcy).await;
+
+    assert_eq!(posts.len(), 3);
+    for post in &posts {
+        assert_eq!(post.source, PostSource::Bcy);
+        assert_eq!(post.images.len(), post.images_number as usize);
+        assert!(post.tags.as_ref().is_some_and(|tags| !tags.is_empty()));
+    }
+    assert_eq!(
+        posts
+            .iter()
+            .filter(|p| p.author_profile_image.is_some())
+            .count(),
+        2
+    );
+
+    let posts = get_posts(&app, "/api/search?source=Bcy&tags=COS").await;
+    assert_eq!(posts.len(), 1);
+    assert_eq!(posts[0].author, "阿九");
+    assert!(get_posts(&app, "/api/search?source=Lofter")
+        .await
+        .is_empty());
+}
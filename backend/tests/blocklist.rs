@@ -0,0 +1,112 @@
+use serde_json::json;
+
+use etl::PostSource;
+use tests::{insert_post, sample_post, scrape_recorded, spawn_api, PostsPage, TestApi};
+
+async fn honkai_links(app: &TestApi, user_key: Option<&str>) -> Vec<String> {
+    let mut request = reqwest::Client::new().get(format!("{}/api/honkai", app.addr));
+    if let Some(user_key) = user_key {
+        request = request.header("x-user-key", user_key);
+    }
+    request
+        .send()
+        .await
+        .unwrap()
+        .json::<PostsPage>()
+        .await
+        .unwrap()
+        .posts
+        .into_iter()
+        .map(|p| p.post_link)
+        .collect()
+}
+
+#[tokio::test]
+async fn test_blocked_authors_and_tags_are_hidden_per_user() {
+    let app = spawn_api().await;
+    let mut by_author = sample_post("https://example.com/1", PostSource::Pixiv);
+    by_author.author = "Blocked".to_owned();
+    let mut by_tag = sample_post("https://example.com/2", PostSource::Pixiv);
+    by_tag.tags = Some(vec!["MMD".to_owned()]);
+    let visible = sample_post("https://example.com/3", PostSource::Pixiv);
+    for post in [&by_author, &by_tag, &visible] {
+        insert_post(&app.api_state.db_pool, post).await;
+    }
+
+    let client = reqwest::Client::new();
+    let blocklist_url = format!("{}/api/blocklist", app.addr);
+    for block in [
+        json!({"kind": "author", "value": "Blocked"}),
+        json!({"kind": "tag", "value": "MMD"}),
+    ] {
+        let response = client
+            .post(&blocklist_url)
+            .header("x-user-key", "user-a")
+            .json(&block)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 204);
+    }
+
+    assert_eq!(
+        honkai_links(&app, Some("user-a")).await,
+        ["https://example.com/3"]
+    );
+    assert_eq!(honkai_links(&app, Some("user-b")).await.len(), 3);
+    assert_eq!(honkai_links(&app, None).await.len(), 3);
+
+    let blocklist = client
+        .get(&blocklist_url)
+        .header("x-user-key", "user-a")
+        .send()
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(blocklist, json!({"authors": ["Blocked"], "tags": ["MMD"]}));
+
+    let response = client
+        .delete(&blocklist_url)
+        .header("x-user-key", "user-a")
+        .json(&json!({"kind": "author", "value": "Blocked"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 204);
+    assert_eq!(honkai_links(&app, Some("user-a")).await.len(), 2);
+}
+
+#[tokio::test]
+async fn test_blocklist_requires_a_user_key() {
+    let app = spawn_api().await;
+
+    let response = reqwest::get(format!("{}/api/blocklist", app.addr))
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn test_blocked_authors_are_not_scraped() {
+    let app = spawn_api().await;
+    let response = reqwest::Client::new()
+        .post(format!("{}/api/blocklist", app.addr))
+        .header("x-user-key", "user-a")
+        .json(&json!({"kind": "author", "value": "爱橙"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 204);
+
+    assert!(!scrape_recorded(&app, "pixiv", PostSource::Pixiv)
+        .await
+        .is_empty());
+    let blocked: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM honkai_posts WHERE author = '爱橙'")
+            .fetch_one(&app.scraper_state.db_pool)
+            .await
+            .unwrap();
+    assert_eq!(blocked, 0);
+}
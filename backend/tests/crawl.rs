@@ -0,0 +1,105 @@
+use std::fs;
+
+use wiremock::matchers::{path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+use etl::{fill_db, fill_db_from, PostSource};
+use tests::{spawn_api, TestApi};
+
+async fn mount_json(app: &TestApi, route: &str, fixture: &str) {
+    let json = fs::read_to_string(fixture).expect("Unable to read the file");
+    Mock::given(path(route))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(json, "application/json"))
+        .mount(&app.mock_server)
+        .await;
+}
+
+async fn requested_urls(app: &TestApi, route: &str) -> Vec<String> {
+    app.mock_server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|r| r.url.path() == route)
+        .map(|r| r.url.to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_twitter_home_follows_pages_until_a_stored_post() {
+    let mut app = spawn_api().await;
+    let json =
+        fs::read_to_string("assets/json/twitter-home.json").expect("Unable to read the file");
+    Mock::given(path("/twitter_home"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(json, "application/json"))
+        .mount(&app.mock_server)
+        .await;
+    let settings = app.scraper_state.sources.get_mut("twitter_home").unwrap();
+    settings.url = format!(
+        "{}/twitter_home?variables=%7B%22count%22%3A20%7D",
+        app.mock_server.uri()
+    );
+    settings.max_pages = 3;
+    settings.page_delay_ms = 0;
+
+    // Nothing is stored yet, so every page up to the cap is requested.
+    fill_db(&app.scraper_state).await.unwrap();
+    assert_eq!(requested_urls(&app, "/twitter_home").await.len(), 3);
+
+    // The first page now contains stored posts.
+    fill_db(&app.scraper_state).await.unwrap();
+    assert_eq!(requested_urls(&app, "/twitter_home").await.len(), 4);
+}
+
+#[tokio::test]
+async fn test_pixiv_follows_pages_until_a_stored_post() {
+    let mut app = spawn_api().await;
+    mount_json(&app, "/pixiv", "assets/json/pixiv.json").await;
+    let settings = app.scraper_state.sources.get_mut("pixiv").unwrap();
+    settings.max_pages = 3;
+    settings.page_delay_ms = 0;
+
+    fill_db(&app.scraper_state).await.unwrap();
+    let urls = requested_urls(&app, "/pixiv").await;
+    assert_eq!(urls.len(), 3);
+    assert!(urls[1].ends_with("p=2"));
+    assert!(urls[2].ends_with("p=3"));
+
+    fill_db(&app.scraper_state).await.unwrap();
+    assert_eq!(requested_urls(&app, "/pixiv").await.len(), 4);
+}
+
+#[tokio::test]
+async fn test_posts_before_a_failed_page_are_saved() {
+    let mut app = spawn_api().await;
+    Mock::given(path("/pixiv"))
+        .and(query_param("p", "2"))
+        .respond_with(ResponseTemplate::new(500))
+        .with_priority(1)
+        .mount(&app.mock_server)
+        .await;
+    mount_json(&app, "/pixiv", "assets/json/pixiv.json").await;
+    let settings = app.scraper_state.sources.get_mut("pixiv").unwrap();
+    settings.max_pages = 3;
+    settings.page_delay_ms = 0;
+
+    let run_id = fill_db_from(&app.scraper_state, PostSource::Pixiv)
+        .await
+        .unwrap();
+    assert_eq!(requested_urls(&app, "/pixiv").await.len(), 2);
+
+    let (success, error_kind, new_count): (bool, Option<String>, i32) =
+        sqlx::query_as("SELECT success, error_kind, new_count FROM source_runs WHERE run_id = $1")
+            .bind(run_id)
+            .fetch_one(&app.api_state.db_pool)
+            .await
+            .unwrap();
+    assert!(!success);
+    assert_eq!(error_kind.as_deref(), Some("status"));
+    assert!(new_count > 0);
+    let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM honkai_posts")
+        .fetch_one(&app.api_state.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(stored, i64::from(new_count));
+}
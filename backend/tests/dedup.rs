@@ -0,0 +1,153 @@
+use std::fs;
+
+use wiremock::matchers::path;
+use wiremock::{Mock, ResponseTemplate};
+
+use etl::{hash_previews, purge_blacklisted, PostSource};
+use tests::{insert_post, sample_post, spawn_api, PostsPage, TestApi};
+
+#[derive(serde::Deserialize)]
+struct FeedPost {
+    post_link: String,
+    alternates: Vec<String>,
+}
+
+async fn mount_image(app: &TestApi, name: &str) {
+    let image = fs::read(format!("assets/img/{name}")).expect("Unable to read the file");
+    Mock::given(path(format!("/img/{name}")))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(image, "image/png"))
+        .mount(&app.mock_server)
+        .await;
+}
+
+async fn insert_post_with_preview(app: &TestApi, post_link: &str, source: PostSource, image: &str) {
+    let mut post = sample_post(post_link, source);
+    post.preview_link = format!("{}/img/{image}", app.mock_server.uri());
+    insert_post(&app.scraper_state.db_pool, &post).await;
+}
+
+async fn get_posts(app: &TestApi, path: &str) -> Vec<FeedPost> {
+    reqwest::get(format!("{}{path}", app.addr))
+        .await
+        .unwrap()
+        .json::<PostsPage<FeedPost>>()
+        .await
+        .unwrap()
+        .posts
+}
+
+#[tokio::test]
+async fn test_cross_posted_artworks_are_grouped() {
+    let app = spawn_api().await;
+    for image in ["artwork.png", "artwork-small.png", "other.png"] {
+        mount_image(&app, image).await;
+    }
+    insert_post_with_preview(&app, "https://pixiv/1", PostSource::Pixiv, "artwork.png").await;
+    insert_post_with_preview(
+        &app,
+        "https://x/1",
+        PostSource::Twitter,
+        "artwork-small.png",
+    )
+    .await;
+    insert_post_with_preview(&app, "https://mihoyo/1", PostSource::Mihoyo, "other.png").await;
+    // The followed accounts' feed is kept apart from the others.
+    insert_post_with_preview(&app, "https://x/2", PostSource::TwitterHome, "artwork.png").await;
+
+    let duplicates = hash_previews(&app.scraper_state).await.unwrap();
+    assert_eq!(duplicates, 1);
+
+    let posts = get_posts(&app, "/api/honkai").await;
+    let links: Vec<_> = posts.iter().map(|p| p.post_link.as_str()).collect();
+    assert_eq!(links, ["https://mihoyo/1", "https://pixiv/1"]);
+    assert_eq!(posts[1].alternates, ["https://x/1"]);
+    assert!(posts[0].alternates.is_empty());
+
+    let posts = get_posts(&app, "/api/myfeed").await;
+    assert_eq!(posts.len(), 1);
+    assert!(posts[0].alternates.is_empty());
+}
+
+#[tokio::test]
+async fn test_posts_are_hashed_once() {
+    let app = spawn_api().await;
+    mount_image(&app, "artwork.png").await;
+    insert_post_with_preview(&app, "https://pixiv/1", PostSource::Pixiv, "artwork.png").await;
+    // Unreachable previews are retried by the next runs, up to three times.
+    insert_post_with_preview(&app, "https://x/1", PostSource::Twitter, "missing.png").await;
+    let unhashed = || async {
+        sqlx::query_scalar::<_, String>(
+            "SELECT post_link FROM honkai_posts WHERE preview_hashed_at IS NULL",
+        )
+        .fetch_all(&app.scraper_state.db_pool)
+        .await
+        .unwrap()
+    };
+
+    assert_eq!(hash_previews(&app.scraper_state).await.unwrap(), 0);
+    assert_eq!(unhashed().await, ["https://x/1"]);
+    insert_post_with_preview(&app, "https://x/2", PostSource::Twitter, "artwork.png").await;
+    assert_eq!(hash_previews(&app.scraper_state).await.unwrap(), 1);
+    assert_eq!(unhashed().await, ["https://x/1"]);
+    assert_eq!(hash_previews(&app.scraper_state).await.unwrap(), 0);
+    assert!(unhashed().await.is_empty());
+
+    let attempts: i32 = sqlx::query_scalar(
+        "SELECT preview_hash_attempts FROM honkai_posts WHERE post_link = 'https://x/1'",
+    )
+    .fetch_one(&app.scraper_state.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(attempts, 3);
+}
+
+#[tokio::test]
+async fn test_only_posts_close_in_time_are_compared() {
+    let app = spawn_api().await;
+    mount_image(&app, "artwork.png").await;
+    insert_post_with_preview(&app, "https://pixiv/1", PostSource::Pixiv, "artwork.png").await;
+    let mut late = sample_post("https://x/1", PostSource::Twitter);
+    late.preview_link = format!("{}/img/artwork.png", app.mock_server.uri());
+    late.created += chrono::Duration::days(30);
+    insert_post(&app.scraper_state.db_pool, &late).await;
+
+    assert_eq!(hash_previews(&app.scraper_state).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_hiding_a_post_keeps_its_duplicates() {
+    let app = spawn_api().await;
+    for image in ["artwork.png", "artwork-small.png"] {
+        mount_image(&app, image).await;
+    }
+    let mut original = sample_post("https://pixiv/1", PostSource::Pixiv);
+    original.author = "Reposted".to_owned();
+    original.preview_link = format!("{}/img/artwork.png", app.mock_server.uri());
+    insert_post(&app.scraper_state.db_pool, &original).await;
+    insert_post_with_preview(
+        &app,
+        "https://x/1",
+        PostSource::Twitter,
+        "artwork-small.png",
+    )
+    .await;
+    insert_post_with_preview(&app, "https://mihoyo/1", PostSource::Mihoyo, "artwork.png").await;
+    assert_eq!(hash_previews(&app.scraper_state).await.unwrap(), 2);
+
+    sqlx::query("INSERT INTO blacklist (kind, pattern) VALUES ('author', 'Reposted')")
+        .execute(&app.scraper_state.db_pool)
+        .await
+        .unwrap();
+    purge_blacklisted(
+        &app.scraper_state.db_pool,
+        &app.scraper_state.blacklist,
+        false,
+    )
+    .await
+    .unwrap();
+
+    let posts = get_posts(&app, "/api/honkai").await;
+    assert_eq!(posts.len(), 1);
+    assert_eq!(posts[0].post_link, "https://x/1");
+    assert_eq!(posts[0].alternates, ["https://mihoyo/1"]);
+}
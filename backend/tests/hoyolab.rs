@@ -0,0 +1,15 @@
+use etl::PostSource;
+use tests::{get_posts, scrape_recorded, spawn_api};
+
+#[tokio::test]
+async fn test_hoyolab_posts_are_searchable_by_topic() {
+    let app = spawn_api().await;
+    scrape_recorded(&app, "hoyolab", PostSource::Hoyolab).await;
+
+    let posts = get_posts(&app, "/api/search?source=Hoyolab&tags=Senadina").await;
+    assert_eq!(posts.len(), 1);
+    let post = &posts[0];
+    assert_eq!(post.source, PostSource::Hoyolab);
+    assert_eq!(post.post_link, "https://www.hoyolab.com/article/41872210");
+    assert_eq!(post.tags.as_deref().unwrap(), ["Fan Art", "Senadina"]);
+}
@@ -0,0 +1,88 @@
+use sqlx::PgPool;
+
+use etl::PostSource;
+use tests::{spawn_api, TestApi};
+
+async fn insert_run(db_pool: &PgPool, source: PostSource, finished_at: &str, success: bool) {
+    sqlx::query(
+        r#"INSERT INTO source_runs (
+            source, started_at, finished_at, success, fetched_count, duration_ms
+        )
+        VALUES ($1, $2::TIMESTAMPTZ, $2::TIMESTAMPTZ, $3, 0, 0)"#,
+    )
+    .bind(source)
+    .bind(finished_at)
+    .bind(success)
+    .execute(db_pool)
+    .await
+    .expect("Failed to insert a source run");
+}
+
+async fn last_update(app: &TestApi, query: &str) -> reqwest::Response {
+    reqwest::get(format!("{}/api/update/last_update{}", app.addr, query))
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_last_update_is_the_oldest_successful_source() {
+    let app = spawn_api().await;
+    let db_pool = &app.api_state.db_pool;
+    insert_run(db_pool, PostSource::Pixiv, "2026-10-18T10:00:00Z", true).await;
+    insert_run(db_pool, PostSource::Mihoyo, "2026-10-18T09:00:00Z", true).await;
+    insert_run(db_pool, PostSource::Mihoyo, "2026-10-18T11:00:00Z", true).await;
+    insert_run(db_pool, PostSource::Twitter, "2026-10-18T08:00:00Z", true).await;
+    insert_run(db_pool, PostSource::Twitter, "2026-10-18T12:00:00Z", false).await;
+    for source in [
+        PostSource::TwitterHome,
+        PostSource::Lofter,
+        PostSource::Hoyolab,
+    ] {
+        insert_run(db_pool, source, "2026-10-18T10:00:00Z", true).await;
+    }
+
+    let response = last_update(&app, "").await;
+    assert_eq!(response.text().await.unwrap(), "2026-10-18T08:00:00+00:00");
+
+    let response = last_update(&app, "?source=Mihoyo").await;
+    assert_eq!(response.text().await.unwrap(), "2026-10-18T11:00:00+00:00");
+}
+
+#[tokio::test]
+async fn test_last_update_of_a_never_successful_source_is_not_found() {
+    let app = spawn_api().await;
+    insert_run(
+        &app.api_state.db_pool,
+        PostSource::TwitterHome,
+        "2026-10-18T10:00:00Z",
+        false,
+    )
+    .await;
+
+    let response = last_update(&app, "?source=TwitterHome").await;
+    assert_eq!(response.status().as_u16(), 404);
+    let response = last_update(&app, "?source=Unknown").await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn test_last_update_is_not_found_while_an_enabled_source_never_succeeded() {
+    let app = spawn_api().await;
+    let db_pool = &app.api_state.db_pool;
+    for source in [
+        PostSource::Pixiv,
+        PostSource::Twitter,
+        PostSource::Mihoyo,
+        PostSource::TwitterHome,
+        PostSource::Lofter,
+    ] {
+        insert_run(db_pool, source, "2026-10-18T10:00:00Z", true).await;
+    }
+
+    let response = last_update(&app, "").await;
+    assert_eq!(response.status().as_u16(), 404);
+
+    insert_run(db_pool, PostSource::Hoyolab, "2026-10-18T09:00:00Z", true).await;
+    let response = last_update(&app, "").await;
+    assert_eq!(response.text().await.unwrap(), "2026-10-18T09:00:00+00:00");
+}
@@ -7,11 +7,12 @@ use tracing_subscriber::{
     fmt::MakeWriter, prelude::__tracing_subscriber_SubscriberExt, EnvFilter, Registry,
 };
 use uuid::Uuid;
-use wiremock::MockServer;
+use wiremock::{matchers::path, Mock, MockServer, ResponseTemplate};
 
 use api::startup::Application as ApiApplication;
 use config_builder::get_configuration;
 use config_structs::{ApiState, DatabaseSettings, ScraperState, SourcesUrls};
+use etl::{fill_db_from, MediaKind, Post, PostImage, PostSource};
 
 fn get_subscriber<Sink>(
     name: String,
@@ -67,12 +68,9 @@ pub async fn spawn_api() -> TestApi {
 
     let mock_server = MockServer::builder().start().await;
     let sources_urls = SourcesUrls {
-        pixiv: format!("{}/pixiv", mock_server.uri()),
-        pixiv_details: format!("{}/pixiv_details", mock_server.uri()),
+        pixiv_details: format!("{}/pixiv_details?illust_id=", mock_server.uri()),
         pixiv_image: format!("{}/pixiv_image", mock_server.uri()),
-        twitter_home: format!("{}/twitter_home", mock_server.uri()),
-        twitter_honkai: format!("{}/twitter_honkai", mock_server.uri()),
-        mihoyo: format!("{}/mihoyo", mock_server.uri()),
+        pixiv_ugoira: format!("{}/pixiv_ugoira/", mock_server.uri()),
     };
 
     let config = {
@@ -81,6 +79,13 @@ pub async fn spawn_api() -> TestApi {
         c.database.database_name = Uuid::new_v4().to_string();
         c.app.port = 0;
         c.app.sources_urls = sources_urls;
+        for (id, source) in c.app.sources.iter_mut() {
+            source.url = format!("{}/{}", mock_server.uri(), id);
+        }
+        c.app.image_cache.dir = std::env::temp_dir()
+            .join(Uuid::new_v4().to_string())
+            .to_string_lossy()
+            .into_owned();
         c
     };
 
@@ -95,6 +100,10 @@ pub async fn spawn_api() -> TestApi {
         api_client: ApiApplication::create_api_client(&config)
             .expect("Failed to create the api client"),
         sources_urls: config.app.sources_urls.clone(),
+        pagination: config.app.pagination.clone(),
+        admin_token: config.app.admin_token.clone(),
+        blacklist: config.app.blacklist.clone(),
+        sources: config.app.sources.clone(),
     };
 
     let scraper_state = ScraperState {
@@ -102,7 +111,8 @@ pub async fn spawn_api() -> TestApi {
         api_client: ApiApplication::create_api_client(&config)
             .expect("Failed to create the api client"),
         blacklist: config.app.blacklist,
-        sources_urls: config.app.sources_urls,
+        sources: config.app.sources,
+        dedup: config.app.dedup,
     };
 
     tokio::spawn(app.run_until_stopped());
@@ -136,7 +146,106 @@ async fn configure_db(config: &DatabaseSettings) -> PgPool {
     connection_pool
 }
 
+/// A page of `/api/honkai` and the other post listings, `T` can narrow the posts
+/// down to the fields a test checks.
+#[derive(serde::Deserialize)]
+pub struct PostsPage<T = Post> {
+    pub posts: Vec<T>,
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+/// Builds a post with placeholder values for the fields the tests don't care about.
+pub fn sample_post(post_link: &str, source: PostSource) -> Post {
+    Post {
+        author: "author".to_owned(),
+        author_link: "https://example.com/author".to_owned(),
+        author_profile_image: None,
+        created: "2023-03-17T22:01:14Z".parse().unwrap(),
+        images: vec![PostImage {
+            url: format!("{post_link}.jpg"),
+            width: None,
+            height: None,
+            variants: Vec::new(),
+        }],
+        images_number: 1,
+        media_kind: MediaKind::Photo,
+        post_link: post_link.to_owned(),
+        preview_link: format!("{post_link}.jpg"),
+        source,
+        tags: None,
+    }
+}
+
+pub async fn insert_post(db_pool: &PgPool, post: &Post) {
+    let post_id: i32 = sqlx::query_scalar(
+        r#"INSERT INTO honkai_posts (
+            post_link,
+            preview_link,
+            images_number,
+            created,
+            author,
+            author_link,
+            source,
+            tags,
+            author_profile_image
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING id"#,
+    )
+    .bind(&post.post_link)
+    .bind(&post.preview_link)
+    .bind(post.images_number)
+    .bind(post.created)
+    .bind(&post.author)
+    .bind(&post.author_link)
+    .bind(post.source)
+    .bind(&post.tags)
+    .bind(&post.author_profile_image)
+    .fetch_one(db_pool)
+    .await
+    .expect("Failed to insert a post");
+    for (position, image) in post.images.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO post_images (post_id, position, url, width, height)
+            VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(post_id)
+        .bind(position as i32)
+        .bind(&image.url)
+        .bind(image.width)
+        .bind(image.height)
+        .execute(db_pool)
+        .await
+        .expect("Failed to insert a post image");
+    }
+}
+
 pub fn assert_is_redirect_to(response: &reqwest::Response, location: &str) {
     assert_eq!(response.status().as_u16(), 303);
     assert_eq!(response.headers().get("Location").unwrap(), location);
 }
+
+/// The posts of a listing of the api, e.g. `/api/search?source=Lofter`.
+pub async fn get_posts(app: &TestApi, path: &str) -> Vec<Post> {
+    reqwest::get(format!("{}{}", app.addr, path))
+        .await
+        .unwrap()
+        .json::<PostsPage>()
+        .await
+        .unwrap()
+        .posts
+}
+
+/// Scrapes `source` from its recorded response in `assets/json/{id}.json` and
+/// returns the posts it saved.
+pub async fn scrape_recorded(app: &TestApi, id: &str, source: PostSource) -> Vec<Post> {
+    let json =
+        std::fs::read_to_string(format!("assets/json/{id}.json")).expect("Unable to read the file");
+    Mock::given(path(format!("/{id}")))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(json, "application/json"))
+        .mount(&app.mock_server)
+        .await;
+    fill_db_from(&app.scraper_state, source).await.unwrap();
+    get_posts(app, &format!("/api/search?source={source:?}")).await
+}
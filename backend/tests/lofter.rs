@@ -0,0 +1,23 @@
+use etl::PostSource;
+use tests::{scrape_recorded, spawn_api};
+
+#[tokio::test]
+async fn test_lofter_posts_are_saved() {
+    let app = spawn_api().await;
+    let posts = scrape_recorded(&app, "lofter", PostSource::Lofter).await;
+
+    // The text post is skipped and the one tagged MMD is blacklisted.
+    assert_eq!(posts.len(), 2);
+    for post in &posts {
+        assert_eq!(post.source, PostSource::Lofter);
+        assert_eq!(post.images.len(), post.images_number as usize);
+        assert!(post.author_profile_image.is_some());
+    }
+    assert!(posts.iter().any(|p| p.author == "没有刀子"));
+    let blacklisted: i32 =
+        sqlx::query_scalar("SELECT blacklisted_count FROM source_runs WHERE source = 'lofter'")
+            .fetch_one(&app.scraper_state.db_pool)
+            .await
+            .unwrap();
+    assert_eq!(blacklisted, 1);
+}
@@ -0,0 +1,89 @@
+use std::fs;
+
+use wiremock::matchers::path;
+use wiremock::{Mock, ResponseTemplate};
+
+use config_structs::ForumSettings;
+use etl::{fill_db_from, PostSource};
+use tests::{spawn_api, TestApi};
+
+async fn mount_mihoyo(app: &TestApi) {
+    let json = fs::read_to_string("assets/json/mihoyo.json").expect("Unable to read the file");
+    Mock::given(path("/mihoyo"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(json, "application/json"))
+        .mount(&app.mock_server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_every_forum_is_followed() {
+    let mut app = spawn_api().await;
+    mount_mihoyo(&app).await;
+    app.scraper_state.sources.get_mut("mihoyo").unwrap().forums = vec![
+        ForumSettings {
+            forum_id: 4,
+            gids: 1,
+        },
+        ForumSettings {
+            forum_id: 29,
+            gids: 2,
+        },
+    ];
+    fill_db_from(&app.scraper_state, PostSource::Mihoyo)
+        .await
+        .unwrap();
+
+    let boards: Vec<(String, String)> = app
+        .mock_server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|r| {
+            let pairs: std::collections::HashMap<_, _> = r.url.query_pairs().into_owned().collect();
+            (pairs["forum_id"].clone(), pairs["gids"].clone())
+        })
+        .collect();
+    assert_eq!(
+        boards,
+        [
+            ("4".to_owned(), "1".to_owned()),
+            ("29".to_owned(), "2".to_owned())
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_topics_are_blacklisted() {
+    let app = spawn_api().await;
+    mount_mihoyo(&app).await;
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string("assets/json/mihoyo.json").unwrap()).unwrap();
+    let topic = json["data"]["list"][0]["topics"][0]["name"]
+        .as_str()
+        .unwrap()
+        .to_owned();
+    sqlx::query("INSERT INTO blacklist (kind, pattern) VALUES ('tag', $1)")
+        .bind(&topic)
+        .execute(&app.scraper_state.db_pool)
+        .await
+        .unwrap();
+    fill_db_from(&app.scraper_state, PostSource::Mihoyo)
+        .await
+        .unwrap();
+
+    let blacklisted: i32 =
+        sqlx::query_scalar("SELECT blacklisted_count FROM source_runs WHERE source = 'mihoyo'")
+            .fetch_one(&app.scraper_state.db_pool)
+            .await
+            .unwrap();
+    assert!(blacklisted > 0);
+    let stored: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM honkai_posts WHERE source = 'mihoyo' AND $1 = ANY(tags)",
+    )
+    .bind(&topic)
+    .fetch_one(&app.scraper_state.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(stored, 0);
+}
@@ -0,0 +1,111 @@
+use sqlx::PgPool;
+
+use etl::{Post, PostSource};
+use tests::{insert_post, sample_post, spawn_api, PostsPage};
+
+async fn insert_posts(db_pool: &PgPool, count: usize, source: PostSource) {
+    for i in 0..count {
+        let link = format!("https://example.com/{source:?}/{i}");
+        insert_post(db_pool, &sample_post(&link, source)).await;
+    }
+}
+
+#[tokio::test]
+async fn test_cursor_pagination_is_stable_while_inserting() {
+    let app = spawn_api().await;
+    let db_pool = &app.api_state.db_pool;
+    insert_posts(db_pool, 5, PostSource::Pixiv).await;
+    insert_posts(db_pool, 2, PostSource::TwitterHome).await;
+    let client = reqwest::Client::new();
+
+    let first = client
+        .get(format!("{}/api/honkai?limit=2", app.addr))
+        .send()
+        .await
+        .unwrap()
+        .json::<PostsPage>()
+        .await
+        .unwrap();
+    assert_eq!(first.posts.len(), 2);
+    assert_eq!(first.posts[0].post_link, "https://example.com/Pixiv/4");
+
+    // New posts must not shift the following pages.
+    insert_post(
+        db_pool,
+        &sample_post("https://example.com/Pixiv/new", PostSource::Pixiv),
+    )
+    .await;
+
+    let mut links: Vec<String> = first.posts.into_iter().map(|p| p.post_link).collect();
+    let mut cursor = first.next_cursor;
+    while let Some(c) = cursor {
+        let page = client
+            .get(format!("{}/api/honkai?limit=2&cursor={}", app.addr, c))
+            .send()
+            .await
+            .unwrap()
+            .json::<PostsPage>()
+            .await
+            .unwrap();
+        links.extend(page.posts.into_iter().map(|p| p.post_link));
+        cursor = page.next_cursor;
+    }
+    let expected: Vec<String> = (0..5)
+        .rev()
+        .map(|i| format!("https://example.com/Pixiv/{i}"))
+        .collect();
+    assert_eq!(links, expected);
+}
+
+#[tokio::test]
+async fn test_legacy_page_parameter_still_works() {
+    let app = spawn_api().await;
+    insert_posts(&app.api_state.db_pool, 3, PostSource::TwitterHome).await;
+
+    let response = reqwest::get(format!("{}/api/myfeed?page=0", app.addr))
+        .await
+        .unwrap();
+    assert_eq!(response.headers()["deprecation"], "true");
+    let posts = response.json::<Vec<Post>>().await.unwrap();
+    assert_eq!(posts.len(), 3);
+
+    let response = reqwest::get(format!("{}/api/myfeed?cursor=garbage", app.addr))
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 422);
+}
+
+#[tokio::test]
+async fn test_posts_can_be_ordered_by_creation_time() {
+    let app = spawn_api().await;
+    // Scraped in the opposite order they were published in.
+    for i in 0..5 {
+        let mut post = sample_post(&format!("https://example.com/{i}"), PostSource::Pixiv);
+        post.created = format!("2026-09-{:02}T10:00:00Z", 10 - i).parse().unwrap();
+        insert_post(&app.api_state.db_pool, &post).await;
+    }
+    let client = reqwest::Client::new();
+
+    let mut links = Vec::new();
+    let mut url = format!("{}/api/honkai?limit=2&order=created", app.addr);
+    loop {
+        let page = client
+            .get(&url)
+            .send()
+            .await
+            .unwrap()
+            .json::<PostsPage>()
+            .await
+            .unwrap();
+        links.extend(page.posts.into_iter().map(|p| p.post_link));
+        let Some(cursor) = page.next_cursor else {
+            break;
+        };
+        url = format!(
+            "{}/api/honkai?limit=2&order=created&cursor={}",
+            app.addr, cursor
+        );
+    }
+    let expected: Vec<String> = (0..5).map(|i| format!("https://example.com/{i}")).collect();
+    assert_eq!(links, expected);
+}
@@ -0,0 +1,120 @@
+use wiremock::matchers::{header, path, query_param};
+use wiremock::{Mock, ResponseTemplate};
+
+use tests::spawn_api;
+
+const IMAGE: &[u8] = &[0xFF, 0xD8, 0xFF, 0xE0, 0, 0x10, b'J', b'F', b'I', b'F'];
+
+async fn mount_pixiv_mocks(app: &tests::TestApi, image_requests: u64) {
+    let details = serde_json::json!({
+        "error": false,
+        "body": {
+            "illust_details": {
+                "url": format!("{}/c/540x540_70/img-master/img/2023/03/17/22/01/14/106302935_p0_master1200.jpg", app.mock_server.uri()),
+                "page_count": "2"
+            }
+        }
+    });
+    Mock::given(path("/pixiv_details"))
+        .and(query_param("illust_id", "106302935"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(details))
+        .named("pixiv_details")
+        .mount(&app.mock_server)
+        .await;
+    Mock::given(path(
+        "/pixiv_image/img/2023/03/17/22/01/14/106302935_p1_master1200.jpg",
+    ))
+    .and(header("referer", "https://www.pixiv.net/"))
+    .respond_with(ResponseTemplate::new(200).set_body_raw(IMAGE, "image/jpeg"))
+    .expect(image_requests)
+    .named("pixiv_image")
+    .mount(&app.mock_server)
+    .await;
+}
+
+#[tokio::test]
+async fn test_pixiv_image_is_proxied_and_cached() {
+    let app = spawn_api().await;
+    mount_pixiv_mocks(&app, 1).await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/pixiv/106302935/1", app.addr);
+
+    let response = client.get(&url).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["content-type"], "image/jpeg");
+    let etag = response.headers()["etag"].to_owned();
+    assert_eq!(response.bytes().await.unwrap(), IMAGE);
+
+    // Give the background task a moment to store the image, the image mock
+    // expects a single call so the next response has to come from the disk cache.
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    let response = client.get(&url).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["content-type"], "image/jpeg");
+    assert_eq!(response.bytes().await.unwrap(), IMAGE);
+
+    let response = client
+        .get(&url)
+        .header("if-none-match", etag)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 304);
+}
+
+#[tokio::test]
+async fn test_pixiv_image_rejects_invalid_requests() {
+    let app = spawn_api().await;
+    mount_pixiv_mocks(&app, 0).await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get(format!("{}/api/pixiv/not-an-id/0", app.addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 422);
+
+    let response = client
+        .get(format!("{}/api/pixiv/106302935/5", app.addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn test_pixiv_ugoira_returns_the_frames() {
+    let app = spawn_api().await;
+    let meta = serde_json::json!({
+        "error": false,
+        "message": "",
+        "body": {
+            "src": "https://i.pximg.net/img-zip-ugoira/img/2023/03/17/22/01/14/106302935_ugoira600x600.zip",
+            "originalSrc": "https://i.pximg.net/img-zip-ugoira/img/2023/03/17/22/01/14/106302935_ugoira1920x1080.zip",
+            "mime_type": "image/jpeg",
+            "frames": [{"file": "000000.jpg", "delay": 100}, {"file": "000001.jpg", "delay": 80}]
+        }
+    });
+    Mock::given(path("/pixiv_ugoira/106302935/ugoira_meta"))
+        .and(header("referer", "https://www.pixiv.net/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(meta))
+        .mount(&app.mock_server)
+        .await;
+
+    let response = reqwest::get(format!("{}/api/pixiv/106302935/ugoira", app.addr))
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    let meta: serde_json::Value = response.json().await.unwrap();
+    assert!(meta["original_src"]
+        .as_str()
+        .unwrap()
+        .ends_with("106302935_ugoira1920x1080.zip"));
+    assert_eq!(meta["frames"][1]["delay"], 80);
+
+    let response = reqwest::get(format!("{}/api/pixiv/106302936/ugoira", app.addr))
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 404);
+}
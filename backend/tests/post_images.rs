@@ -0,0 +1,32 @@
+use std::fs;
+
+use wiremock::matchers::path;
+use wiremock::{Mock, ResponseTemplate};
+
+use etl::fill_db;
+use tests::{spawn_api, PostsPage};
+
+#[tokio::test]
+async fn test_posts_list_every_image() {
+    let app = spawn_api().await;
+    let json = fs::read_to_string("assets/json/pixiv.json").expect("Unable to read the file");
+    Mock::given(path("/pixiv"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(json, "application/json"))
+        .mount(&app.mock_server)
+        .await;
+    fill_db(&app.scraper_state).await.unwrap();
+
+    let page: PostsPage = reqwest::get(format!("{}/api/honkai?limit=100", app.addr))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    assert!(page.posts.iter().any(|p| p.images_number > 1));
+    for post in page.posts {
+        assert_eq!(post.images.len(), post.images_number as usize);
+        assert_eq!(post.images[0].url, post.preview_link);
+        assert!(post.images[0].width.is_some());
+    }
+}
@@ -0,0 +1,327 @@
+use std::{fs, time::Duration};
+
+use wiremock::matchers::path;
+use wiremock::{Mock, ResponseTemplate};
+
+use serde_json::json;
+
+use tokio::time::timeout;
+
+use etl::{fill_db, fill_db_from, run_pending, PostSource};
+use tests::{spawn_api, TestApi};
+
+#[derive(serde::Deserialize)]
+struct RunsPage {
+    runs: Vec<ScrapeRun>,
+    next_cursor: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct ScrapeRun {
+    id: i32,
+    finished_at: Option<String>,
+    fetched: i64,
+    new: i64,
+    updated: i64,
+    duplicate: i64,
+    blacklisted: i64,
+    failed_sources: i64,
+}
+
+#[derive(serde::Deserialize)]
+struct ScrapeRunDetails {
+    id: i32,
+    status: String,
+    source_runs: Vec<SourceRun>,
+}
+
+#[derive(serde::Deserialize)]
+struct RequestedRun {
+    id: i32,
+    status: String,
+}
+
+#[derive(serde::Deserialize)]
+struct SourceRun {
+    source: PostSource,
+    success: bool,
+    error_kind: Option<String>,
+    fetched_count: i32,
+    new_count: i32,
+    updated_count: i32,
+}
+
+async fn mount_pixiv(app: &TestApi) {
+    let json = fs::read_to_string("assets/json/pixiv.json").expect("Unable to read the file");
+    Mock::given(path("/pixiv"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(json, "application/json"))
+        .mount(&app.mock_server)
+        .await;
+}
+
+const ADMIN_TOKEN: &str = "Token test-admin-token";
+
+async fn get_as_admin(app: &TestApi, path: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .get(format!("{}{}", app.addr, path))
+        .header("Authorization", ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn get<T: serde::de::DeserializeOwned>(app: &TestApi, path: &str) -> T {
+    get_as_admin(app, path).await.json().await.unwrap()
+}
+
+#[tokio::test]
+async fn test_runs_record_post_counts() {
+    let app = spawn_api().await;
+    mount_pixiv(&app).await;
+
+    let first = fill_db(&app.scraper_state).await.unwrap();
+    // Blacklist the author of one of the stored posts.
+    sqlx::query(
+        "INSERT INTO blacklist (kind, pattern)
+        SELECT 'author', author FROM honkai_posts LIMIT 1",
+    )
+    .execute(&app.api_state.db_pool)
+    .await
+    .unwrap();
+    let second = fill_db(&app.scraper_state).await.unwrap();
+
+    let page: RunsPage = get(&app, "/api/runs?limit=1").await;
+    assert_eq!(page.runs.len(), 1);
+    let latest = &page.runs[0];
+    assert_eq!(latest.id, second);
+    assert!(latest.finished_at.is_some());
+    assert_eq!(latest.failed_sources, 5);
+
+    let cursor = page.next_cursor.unwrap();
+    let page: RunsPage = get(&app, &format!("/api/runs?limit=1&cursor={cursor}")).await;
+    let earliest = &page.runs[0];
+    assert_eq!(earliest.id, first);
+    assert!(page.next_cursor.is_none());
+
+    assert!(earliest.new > 0);
+    assert_eq!(earliest.new + earliest.blacklisted, earliest.fetched);
+    // Posts already stored count as duplicates, unless they are blacklisted now.
+    assert_eq!(latest.new, 0);
+    assert!(latest.blacklisted > earliest.blacklisted);
+    assert_eq!(latest.duplicate + latest.blacklisted, latest.fetched);
+}
+
+#[tokio::test]
+async fn test_changed_posts_are_updated() {
+    let app = spawn_api().await;
+    mount_pixiv(&app).await;
+    fill_db(&app.scraper_state).await.unwrap();
+
+    let (id, post_link): (i32, String) = sqlx::query_as(
+        "SELECT id, post_link FROM honkai_posts WHERE source = 'pixiv' ORDER BY id LIMIT 1",
+    )
+    .fetch_one(&app.api_state.db_pool)
+    .await
+    .unwrap();
+    let illust_id = post_link.rsplit('/').next().unwrap();
+    let mut json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string("assets/json/pixiv.json").unwrap()).unwrap();
+    let illust = json["body"]["illusts"]
+        .as_array_mut()
+        .unwrap()
+        .iter_mut()
+        .find(|illust| illust["id"] == illust_id)
+        .unwrap();
+    illust["author_details"]["user_name"] = json!("renamed");
+    illust["user_id"] = json!("1");
+    illust["tags"] = json!(["edited"]);
+    app.mock_server.reset().await;
+    Mock::given(path("/pixiv"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json))
+        .mount(&app.mock_server)
+        .await;
+    let run_id = fill_db(&app.scraper_state).await.unwrap();
+
+    let run: ScrapeRunDetails = get(&app, &format!("/api/runs/{run_id}")).await;
+    let pixiv = run.source_runs.iter().find(|s| s.success).unwrap();
+    assert_eq!(pixiv.new_count, 0);
+    assert_eq!(pixiv.updated_count, 1);
+    let page: RunsPage = get(&app, "/api/runs?limit=1").await;
+    let latest = &page.runs[0];
+    assert_eq!(latest.updated, 1);
+    assert_eq!(
+        latest.updated + latest.duplicate + latest.blacklisted,
+        latest.fetched
+    );
+
+    let (author, author_link, tags, updated): (String, String, Vec<String>, bool) = sqlx::query_as(
+        "SELECT author, author_link, tags, updated_at IS NOT NULL
+            FROM honkai_posts WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_one(&app.api_state.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(author, "renamed");
+    assert_eq!(author_link, "https://www.pixiv.net/en/users/1");
+    assert_eq!(tags, ["edited"]);
+    assert!(updated);
+    let unchanged: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM honkai_posts WHERE updated_at IS NULL")
+            .fetch_one(&app.api_state.db_pool)
+            .await
+            .unwrap();
+    assert!(unchanged > 0);
+}
+
+#[tokio::test]
+async fn test_run_details_list_every_source() {
+    let app = spawn_api().await;
+    mount_pixiv(&app).await;
+    let run_id = fill_db(&app.scraper_state).await.unwrap();
+
+    let run: ScrapeRunDetails = get(&app, &format!("/api/runs/{run_id}")).await;
+    assert_eq!(run.id, run_id);
+    assert_eq!(run.source_runs.len(), 6);
+    let pixiv = run.source_runs.iter().find(|s| s.success).unwrap();
+    assert!(pixiv.new_count > 0);
+    assert!(pixiv.new_count <= pixiv.fetched_count);
+
+    let response = get_as_admin(&app, &format!("/api/runs/{}", run_id + 1)).await;
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn test_a_failed_save_is_recorded_on_its_source() {
+    let app = spawn_api().await;
+    mount_pixiv(&app).await;
+    sqlx::raw_sql(
+        "CREATE FUNCTION reject_pixiv() RETURNS TRIGGER LANGUAGE plpgsql AS $$
+        BEGIN RAISE EXCEPTION 'rejected'; END $$;
+        CREATE TRIGGER reject_pixiv BEFORE INSERT ON honkai_posts
+        FOR EACH ROW WHEN (NEW.source = 'pixiv') EXECUTE FUNCTION reject_pixiv();",
+    )
+    .execute(&app.api_state.db_pool)
+    .await
+    .unwrap();
+
+    let run_id = fill_db(&app.scraper_state).await.unwrap();
+
+    let run: ScrapeRunDetails = get(&app, &format!("/api/runs/{run_id}")).await;
+    assert_eq!(run.status, "finished");
+    assert_eq!(run.source_runs.len(), 6);
+    let pixiv = run
+        .source_runs
+        .iter()
+        .find(|s| s.source == PostSource::Pixiv)
+        .unwrap();
+    assert!(!pixiv.success);
+    assert_eq!(pixiv.error_kind.as_deref(), Some("database"));
+}
+
+async fn request_update(app: &TestApi, body: Option<serde_json::Value>) -> reqwest::Response {
+    let mut request = reqwest::Client::new()
+        .post(format!("{}/api/update", app.addr))
+        .header("Authorization", ADMIN_TOKEN);
+    if let Some(body) = body {
+        request = request.json(&body);
+    }
+    request.send().await.unwrap()
+}
+
+#[tokio::test]
+async fn test_requested_runs_are_merged_until_picked_up() {
+    let app = spawn_api().await;
+    mount_pixiv(&app).await;
+
+    let response = request_update(&app, Some(json!({"sources": ["Pixiv"]}))).await;
+    assert_eq!(response.status().as_u16(), 202);
+    let first: RequestedRun = response.json().await.unwrap();
+    assert_eq!(first.status, "pending");
+    let second: RequestedRun = request_update(&app, Some(json!({"sources": ["Mihoyo"]})))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(second.id, first.id);
+
+    assert_eq!(
+        run_pending(&app.scraper_state).await.unwrap(),
+        Some(first.id)
+    );
+    assert_eq!(run_pending(&app.scraper_state).await.unwrap(), None);
+
+    let run: ScrapeRunDetails = get(&app, &format!("/api/runs/{}", first.id)).await;
+    assert_eq!(run.status, "finished");
+    let mut sources: Vec<_> = run.source_runs.iter().map(|s| s.source).collect();
+    sources.sort_by_key(|s| format!("{s:?}"));
+    assert_eq!(sources, [PostSource::Mihoyo, PostSource::Pixiv]);
+
+    let third: RequestedRun = request_update(&app, None).await.json().await.unwrap();
+    assert_ne!(third.id, first.id);
+}
+
+#[tokio::test]
+async fn test_cancelled_runs_are_failed() {
+    let app = spawn_api().await;
+    Mock::given(path("/pixiv"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(30)))
+        .mount(&app.mock_server)
+        .await;
+
+    // Dropped like a scheduled run going over its maximum running time.
+    let scrape = fill_db_from(&app.scraper_state, PostSource::Pixiv);
+    assert!(timeout(Duration::from_millis(500), scrape).await.is_err());
+
+    let page: RunsPage = get(&app, "/api/runs?limit=1").await;
+    let cancelled = page.runs[0].id;
+    let mut run: ScrapeRunDetails = get(&app, &format!("/api/runs/{cancelled}")).await;
+    for _ in 0..50 {
+        if run.status != "running" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        run = get(&app, &format!("/api/runs/{cancelled}")).await;
+    }
+    assert_eq!(run.status, "failed");
+
+    let requested: RequestedRun = request_update(&app, Some(json!({"sources": ["Pixiv"]})))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_ne!(requested.id, cancelled);
+    assert_eq!(requested.status, "pending");
+}
+
+#[tokio::test]
+async fn test_requesting_a_run_requires_the_admin_token() {
+    let app = spawn_api().await;
+    let response = reqwest::Client::new()
+        .post(format!("{}/api/update", app.addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = request_update(&app, Some(json!({"sources": []}))).await;
+    assert_eq!(response.status().as_u16(), 422);
+}
+
+#[tokio::test]
+async fn test_runs_require_the_admin_token() {
+    let app = spawn_api().await;
+    let run_id = fill_db(&app.scraper_state).await.unwrap();
+
+    for path in ["/api/runs".to_owned(), format!("/api/runs/{run_id}")] {
+        let response = reqwest::get(format!("{}{}", app.addr, path)).await.unwrap();
+        assert_eq!(response.status().as_u16(), 401);
+    }
+}
+
+#[tokio::test]
+async fn test_runs_are_only_ordered_by_id() {
+    let app = spawn_api().await;
+    let response = get_as_admin(&app, "/api/runs?order=created").await;
+    assert_eq!(response.status().as_u16(), 422);
+}
@@ -0,0 +1,96 @@
+use etl::PostSource;
+use tests::{insert_post, sample_post, spawn_api, PostsPage, TestApi};
+
+async fn search(app: &TestApi, query: &str) -> Vec<String> {
+    reqwest::get(format!("{}/api/search?{}", app.addr, query))
+        .await
+        .unwrap()
+        .json::<PostsPage>()
+        .await
+        .unwrap()
+        .posts
+        .into_iter()
+        .map(|p| p.post_link)
+        .collect()
+}
+
+async fn seed(app: &TestApi) {
+    let posts = [
+        (
+            "https://example.com/1",
+            PostSource::Pixiv,
+            "Alice",
+            &["符华", "崩坏3rd"][..],
+            "2026-09-01T10:00:00+00:00",
+        ),
+        (
+            "https://example.com/2",
+            PostSource::Pixiv,
+            "Bob",
+            &["符华"][..],
+            "2026-09-05T10:00:00+00:00",
+        ),
+        (
+            "https://example.com/3",
+            PostSource::Twitter,
+            "Alice",
+            &["琪亚娜"][..],
+            "2026-09-10T10:00:00+00:00",
+        ),
+    ];
+    for (link, source, author, tags, created) in posts {
+        let mut post = sample_post(link, source);
+        post.author = author.to_owned();
+        post.tags = Some(tags.iter().map(|t| t.to_string()).collect());
+        post.created = created.parse().unwrap();
+        insert_post(&app.api_state.db_pool, &post).await;
+    }
+}
+
+#[tokio::test]
+async fn test_search_filters() {
+    let app = spawn_api().await;
+    seed(&app).await;
+
+    assert_eq!(
+        search(&app, "tags=符华,琪亚娜").await,
+        [
+            "https://example.com/3",
+            "https://example.com/2",
+            "https://example.com/1"
+        ]
+    );
+    assert_eq!(
+        search(&app, "tags=符华,崩坏3rd&tag_mode=all").await,
+        ["https://example.com/1"]
+    );
+    assert_eq!(
+        search(&app, "author=Alice&source=Pixiv").await,
+        ["https://example.com/1"]
+    );
+    assert_eq!(
+        search(&app, "since=2026-09-02&until=2026-09-06").await,
+        ["https://example.com/2"]
+    );
+    assert_eq!(search(&app, "q=bob").await, ["https://example.com/2"]);
+    assert_eq!(
+        search(&app, "since=2026-09-01&until=2026-09-05").await,
+        ["https://example.com/2", "https://example.com/1"]
+    );
+    assert_eq!(search(&app, "q=崩坏").await, ["https://example.com/1"]);
+    assert_eq!(
+        search(&app, "q=alice 亚娜").await,
+        ["https://example.com/3"]
+    );
+    assert!(search(&app, "q=%").await.is_empty());
+}
+
+#[tokio::test]
+async fn test_search_rejects_invalid_dates() {
+    let app = spawn_api().await;
+
+    let response = reqwest::get(format!("{}/api/search?since=yesterday", app.addr))
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 422);
+}
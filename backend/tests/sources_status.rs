@@ -0,0 +1,107 @@
+use std::{fs, time::Duration};
+
+use wiremock::matchers::path;
+use wiremock::{Mock, ResponseTemplate};
+
+use etl::{fill_db, PostSource};
+use tests::{spawn_api, TestApi};
+
+#[derive(serde::Deserialize)]
+struct SourceStatus {
+    source: PostSource,
+    success: bool,
+    error_kind: Option<String>,
+    fetched_count: i32,
+    last_success_at: Option<String>,
+}
+
+async fn mount_json(app: &TestApi, route: &str, fixture: &str) {
+    let json = fs::read_to_string(fixture).expect("Unable to read the file");
+    Mock::given(path(route))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(json, "application/json"))
+        .mount(&app.mock_server)
+        .await;
+}
+
+async fn statuses(app: &TestApi) -> Vec<SourceStatus> {
+    reqwest::get(format!("{}/api/sources/status", app.addr))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+fn status_of(statuses: &[SourceStatus], source: PostSource) -> &SourceStatus {
+    statuses.iter().find(|s| s.source == source).unwrap()
+}
+
+#[tokio::test]
+async fn test_failed_sources_are_reported() {
+    let app = spawn_api().await;
+    mount_json(&app, "/pixiv", "assets/json/pixiv.json").await;
+    mount_json(&app, "/twitter_home", "assets/json/twitter-home.json").await;
+    Mock::given(path("/twitter_honkai"))
+        .respond_with(ResponseTemplate::new(401))
+        .mount(&app.mock_server)
+        .await;
+    Mock::given(path("/mihoyo"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw("{}", "application/json"))
+        .mount(&app.mock_server)
+        .await;
+
+    fill_db(&app.scraper_state).await.unwrap();
+
+    let statuses = statuses(&app).await;
+    assert_eq!(statuses.len(), 6);
+    let pixiv = status_of(&statuses, PostSource::Pixiv);
+    assert!(pixiv.success);
+    assert!(pixiv.fetched_count > 0);
+    assert!(pixiv.last_success_at.is_some());
+    let twitter = status_of(&statuses, PostSource::Twitter);
+    assert!(!twitter.success);
+    assert_eq!(twitter.error_kind.as_deref(), Some("status"));
+    assert!(twitter.last_success_at.is_none());
+    let mihoyo = status_of(&statuses, PostSource::Mihoyo);
+    assert_eq!(mihoyo.error_kind.as_deref(), Some("decode"));
+}
+
+#[tokio::test]
+async fn test_last_success_survives_a_failure() {
+    let app = spawn_api().await;
+    mount_json(&app, "/pixiv", "assets/json/pixiv.json").await;
+    fill_db(&app.scraper_state).await.unwrap();
+
+    app.mock_server.reset().await;
+    Mock::given(path("/pixiv"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&app.mock_server)
+        .await;
+    fill_db(&app.scraper_state).await.unwrap();
+
+    let statuses = statuses(&app).await;
+    let pixiv = status_of(&statuses, PostSource::Pixiv);
+    assert!(!pixiv.success);
+    assert_eq!(pixiv.fetched_count, 0);
+    assert!(pixiv.last_success_at.is_some());
+}
+
+#[tokio::test]
+async fn test_slow_and_disabled_sources() {
+    let mut app = spawn_api().await;
+    Mock::given(path("/pixiv"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(3)))
+        .mount(&app.mock_server)
+        .await;
+    let sources = &mut app.scraper_state.sources;
+    sources.get_mut("pixiv").unwrap().timeout_secs = 1;
+    sources.get_mut("mihoyo").unwrap().enabled = false;
+
+    fill_db(&app.scraper_state).await.unwrap();
+
+    let statuses = statuses(&app).await;
+    assert_eq!(statuses.len(), 5);
+    assert!(statuses.iter().all(|s| s.source != PostSource::Mihoyo));
+    let pixiv = status_of(&statuses, PostSource::Pixiv);
+    assert_eq!(pixiv.error_kind.as_deref(), Some("timeout"));
+}
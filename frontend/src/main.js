@@ -5,6 +5,7 @@ import piniaPluginPersistedstate from 'pinia-plugin-persistedstate'
 import App from './App.vue'
 
 import router from './router'
+import { getUserKey, setUserKey } from './userKey'
 
 import 'bootstrap/dist/css/bootstrap.css';
 import {Dropdown} from 'bootstrap';
@@ -17,6 +18,16 @@ import "vue-toastification/dist/index.css";
 axios.defaults.withCredentials = true;
 axios.defaults.baseURL = import.meta.env.VITE_APP_BACKEND_URL;  // backend
 
+setUserKey(getUserKey());
+
+// Move the authors blocked before the blocklist lived on the backend
+const legacyFilterList = JSON.parse(localStorage.getItem('filterList') || '[]');
+if (Array.isArray(legacyFilterList) && legacyFilterList.length > 0) {
+    Promise.all(legacyFilterList.map(author => axios.post('/blocklist', { kind: 'author', value: author })))
+        .then(() => localStorage.removeItem('filterList'))
+        .catch(error => console.error('Failed to move filterList to the backend:', error));
+}
+
 const app = createApp(App)
 
 const pinia = createPinia()
@@ -1,6 +1,7 @@
 import { createRouter, createWebHistory } from 'vue-router'
 
 import PostsView from './views/PostsView.vue'
+import SettingsView from './views/SettingsView.vue'
 
 const routes = [
   {
@@ -15,6 +16,12 @@ const routes = [
     component: PostsView,
     meta: {title: 'My feed'},
   },
+  {
+    path: '/settings',
+    name: 'settings',
+    component: SettingsView,
+    meta: {title: 'Settings'},
+  },
   {
     path: '/:catchAll(.*)', redirect: '/honkai' 
   }
@@ -5,16 +5,21 @@ export const useStore = defineStore({
     id: 'auth',
     state: () => ({
         lastUpdate: null,
+        failingSources: [],
     }),
     persist: true,
     getters: {
         LastUpdate: state => state.lastUpdate,
+        FailingSources: state => state.failingSources,
     },
     actions: {
         async lastUpdateSetter() {
             axios.get('update/last_update').then(res => {
                 this.lastUpdate = new Date(res.data).toLocaleTimeString('ru')
               })
+            axios.get('sources/status').then(res => {
+                this.failingSources = res.data.filter(s => !s.success).map(s => s.source)
+              })
         }
     }
 })
\ No newline at end of file
@@ -0,0 +1,21 @@
+import axios from 'axios';
+
+// Identifies the user's blocklist on the backend, it's a secret: whoever has it
+// can read and edit the blocklist. Copy it to another device to share the blocklist.
+const STORAGE_KEY = 'userKey';
+// The backend rejects longer keys
+export const MAX_USER_KEY_LENGTH = 128;
+
+export function getUserKey() {
+    let userKey = localStorage.getItem(STORAGE_KEY);
+    if (!userKey) {
+        userKey = crypto.randomUUID();
+        localStorage.setItem(STORAGE_KEY, userKey);
+    }
+    return userKey;
+}
+
+export function setUserKey(userKey) {
+    localStorage.setItem(STORAGE_KEY, userKey);
+    axios.defaults.headers.common['X-User-Key'] = userKey;
+}